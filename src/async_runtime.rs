@@ -0,0 +1,174 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Abstracts over the async runtime in use,
+//! as selected with the `async-std` or `tokio` feature.
+//! Without either, we stay runtime-agnostic,
+//! and do blocking I/O on the thread-pool of the `blocking` crate.
+//!
+//! All I/O types are handed out through the `futures` I/O traits.
+
+// Spawning is only required for file traversal.
+#![cfg_attr(not(feature = "file_traversal"), allow(dead_code))]
+
+use std::{future::Future, io, path::Path};
+
+use futures::io::{AsyncRead, AsyncWrite};
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+/// Runs a blocking function on a thread dedicated to blocking operations,
+/// and returns its result.
+#[cfg(feature = "async-std")]
+pub async fn spawn_blocking<F, T>(func: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    async_std::task::spawn_blocking(func).await
+}
+
+/// Runs a blocking function on a thread dedicated to blocking operations,
+/// and returns its result.
+///
+/// # Panics
+///
+/// If `func` panics.
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn spawn_blocking<F, T>(func: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(func).await {
+        Ok(ret) => ret,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Runs a blocking function on a thread dedicated to blocking operations,
+/// and returns its result.
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub async fn spawn_blocking<F, T>(func: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    blocking::unblock(func).await
+}
+
+/// Runs `future` as a separate task,
+/// so it may run in parallel to the current one,
+/// and returns its output.
+#[cfg(feature = "async-std")]
+pub async fn spawn<Fut>(future: Fut) -> Fut::Output
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    async_std::task::spawn(future).await
+}
+
+/// Runs `future` as a separate task,
+/// so it may run in parallel to the current one,
+/// and returns its output.
+///
+/// # Panics
+///
+/// If `future` panics.
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn spawn<Fut>(future: Fut) -> Fut::Output
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    match tokio::spawn(future).await {
+        Ok(ret) => ret,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+/// Without a runtime, we can not spawn tasks,
+/// so this runs `future` in the current one;
+/// it still runs concurrently to other futures polled by the caller.
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub async fn spawn<Fut>(future: Fut) -> Fut::Output
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    future.await
+}
+
+/// Opens a file for reading.
+#[cfg(feature = "async-std")]
+pub async fn open(path: &Path) -> io::Result<impl AsyncRead + Unpin + Send + Sync + use<>> {
+    async_std::fs::File::open(path).await
+}
+
+/// Opens a file for reading.
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn open(path: &Path) -> io::Result<impl AsyncRead + Unpin + Send + Sync + use<>> {
+    Ok(tokio::fs::File::open(path).await?.compat())
+}
+
+/// Opens a file for reading.
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub async fn open(path: &Path) -> io::Result<impl AsyncRead + Unpin + Send + Sync + use<>> {
+    let file_path = path.to_path_buf();
+    Ok(blocking::Unblock::new(
+        spawn_blocking(move || std::fs::File::open(file_path)).await?,
+    ))
+}
+
+/// Creates (or truncates) a file for writing.
+#[cfg(feature = "async-std")]
+pub async fn create(path: &Path) -> io::Result<impl AsyncWrite + Unpin + Send + Sync + use<>> {
+    async_std::fs::File::create(path).await
+}
+
+/// Creates (or truncates) a file for writing.
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn create(path: &Path) -> io::Result<impl AsyncWrite + Unpin + Send + Sync + use<>> {
+    Ok(tokio::fs::File::create(path).await?.compat_write())
+}
+
+/// Creates (or truncates) a file for writing.
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub async fn create(path: &Path) -> io::Result<impl AsyncWrite + Unpin + Send + Sync + use<>> {
+    let file_path = path.to_path_buf();
+    Ok(blocking::Unblock::new(
+        spawn_blocking(move || std::fs::File::create(file_path)).await?,
+    ))
+}
+
+#[cfg(feature = "async-std")]
+pub fn stdin() -> impl AsyncRead + Unpin + Send + Sync {
+    async_std::io::stdin()
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub fn stdin() -> impl AsyncRead + Unpin + Send + Sync {
+    tokio::io::stdin().compat()
+}
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub fn stdin() -> impl AsyncRead + Unpin + Send + Sync {
+    blocking::Unblock::new(io::stdin())
+}
+
+#[cfg(feature = "async-std")]
+pub fn stdout() -> impl AsyncWrite + Unpin + Send + Sync {
+    async_std::io::stdout()
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub fn stdout() -> impl AsyncWrite + Unpin + Send + Sync {
+    tokio::io::stdout().compat_write()
+}
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+pub fn stdout() -> impl AsyncWrite + Unpin + Send + Sync {
+    blocking::Unblock::new(io::stdout())
+}
@@ -3,18 +3,76 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    fs::{DirEntry, FileType, Metadata},
+    io,
+    iter::Peekable,
+    ops::BitOr,
+    path::{Path as StdPath, PathBuf as StdPathBuf},
+    str::Chars,
+    sync::{Arc, OnceLock},
+};
+#[cfg(feature = "file_traversal")]
+use std::{future::Future, num::NonZeroUsize};
+
+#[cfg(feature = "file_traversal")]
+use crate::async_runtime;
 #[cfg(feature = "serde")]
 use crate::path_buf::PathBuf;
-#[cfg(not(feature = "serde"))]
+#[cfg(feature = "async-std")]
+use async_std::path::Path;
+#[cfg(all(feature = "async-std", not(feature = "serde")))]
 use async_std::path::PathBuf;
-use {async_std::path::Path, async_walkdir::WalkDir, futures::StreamExt};
+#[cfg(feature = "file_traversal")]
+use futures::{Stream, TryStreamExt, future, stream};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "async-std"))]
+use std::path::Path;
+#[cfg(not(any(feature = "async-std", feature = "serde")))]
+use std::path::PathBuf;
+
+pub mod blocking;
+#[cfg(feature = "scan_cache")]
+pub mod cache;
+pub mod filters;
+#[cfg(any(feature = "hash_blake3", feature = "hash_sha256"))]
+pub mod hash;
+#[cfg(feature = "file_watch")]
+pub mod watch;
 
 pub type PathFilterRet = Result<bool, std::io::Error>;
 pub type PathFilter = dyn Fn(&Path) -> PathFilterRet + Send + Sync;
+/// Like [`PathFilter`], but with access to everything known about an entry,
+/// see [`scan_entries`].
+pub type EntryFilter = dyn Fn(&Entry) -> PathFilterRet + Send + Sync;
+
+/// A filter accepting all paths.
+/// Use this as directory filter, if you do not want to prune any directories.
+///
+/// # Errors
+///
+/// Never.
+pub const fn accept_all(_path: &Path) -> PathFilterRet {
+    Ok(true)
+}
 
-pub fn create_combined_filter(
-    filters: Vec<Box<impl Fn(&Path) -> PathFilterRet + Send + Sync>>,
-) -> impl Fn(&Path) -> PathFilterRet + Send + Sync {
+/// Creates a filter that accepts a path
+/// only if all of the given filters accept it,
+/// e.g. some of the ones in [`filters`].
+///
+/// Filters are evaluated in order,
+/// and evaluation stops at the first one rejecting the path.
+/// To combine filters of different types, box them as [`PathFilter`]s,
+/// e.g. with [`PathFilterExt::boxed`].
+pub fn create_combined_filter<F>(
+    filters: Vec<Box<F>>,
+) -> impl Fn(&Path) -> PathFilterRet + Send + Sync
+where
+    F: Fn(&Path) -> PathFilterRet + Send + Sync + ?Sized,
+{
     move |file: &Path| {
         for filter in &filters {
             if !filter(file)? {
@@ -25,6 +83,78 @@ pub fn create_combined_filter(
     }
 }
 
+/// Creates a filter that accepts a path
+/// if any of the given filters accepts it.
+///
+/// Filters are evaluated in order,
+/// and evaluation stops at the first one accepting the path.
+/// See also [`create_combined_filter`].
+pub fn create_any_filter<F>(filters: Vec<Box<F>>) -> impl Fn(&Path) -> PathFilterRet + Send + Sync
+where
+    F: Fn(&Path) -> PathFilterRet + Send + Sync + ?Sized,
+{
+    move |file: &Path| {
+        for filter in &filters {
+            if filter(file)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Boolean combinators for path filters,
+/// implemented for all functions and closures usable as [`PathFilter`].
+///
+/// They allow to compose filters of different types, for example:
+///
+/// ```
+/// use cli_utils_hoijui::file_traversal::{
+///     PathFilterExt,
+///     filters::{extensions, shebang, size},
+/// };
+///
+/// let small_sources = extensions(["rs"]).or(shebang("sh")).and(size(1024..).not());
+/// ```
+///
+/// Evaluation is short-circuiting, like with `&&` and `||`,
+/// so put cheap filters first.
+pub trait PathFilterExt: Fn(&Path) -> PathFilterRet + Send + Sync + Sized + 'static {
+    /// Accepts a path if both `self` and `other` accept it.
+    #[must_use]
+    fn and<O>(self, other: O) -> Box<PathFilter>
+    where
+        O: Fn(&Path) -> PathFilterRet + Send + Sync + 'static,
+    {
+        Box::new(move |file: &Path| Ok(self(file)? && other(file)?))
+    }
+
+    /// Accepts a path if `self` or `other` accepts it.
+    #[must_use]
+    fn or<O>(self, other: O) -> Box<PathFilter>
+    where
+        O: Fn(&Path) -> PathFilterRet + Send + Sync + 'static,
+    {
+        Box::new(move |file: &Path| Ok(self(file)? || other(file)?))
+    }
+
+    /// Accepts a path if `self` rejects it.
+    /// Errors are passed on unchanged.
+    #[must_use]
+    fn not(self) -> Box<PathFilter> {
+        Box::new(move |file: &Path| Ok(!self(file)?))
+    }
+
+    /// Turns this into a trait object,
+    /// e.g. to put it into a `Vec` with filters of other types.
+    #[must_use]
+    fn boxed(self) -> Box<PathFilter> {
+        Box::new(self)
+    }
+}
+
+impl<F> PathFilterExt for F where F: Fn(&Path) -> PathFilterRet + Send + Sync + 'static {}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Supplied Input file is missing file name: '{0:#?}'")]
@@ -35,48 +165,1111 @@ pub enum Error {
 
     #[error("I/O Error: '{0:#?}'")]
     IO(#[from] std::io::Error),
+
+    #[error("Traversal aborted: {0}")]
+    Walk(SkippedEntry),
+
+    #[cfg(any(feature = "hash_blake3", feature = "hash_sha256"))]
+    #[error("Failed to hash file '{0:?}': {1}")]
+    Hash(PathBuf, std::io::Error),
+
+    #[cfg(feature = "scan_cache")]
+    #[error("Failed to read or write the scan cache '{0:?}': {1}")]
+    Cache(StdPathBuf, std::io::Error),
+
+    #[cfg(feature = "file_watch")]
+    #[error("Failed to watch for changes: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+/// What to do when a directory or entry can not be read during traversal,
+/// e.g. because of missing permissions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ErrorPolicy {
+    /// Abort the traversal with [`Error::Walk`].
+    FailFast,
+    /// Skip the entry, and record it in the [`ScanSummary`].
+    Collect,
+    /// Skip the entry, record it in the [`ScanSummary`],
+    /// and log a warning (if the `logging` feature is enabled).
+    #[default]
+    Log,
+}
+
+/// A selection of file types, see [`TraversalOptions::file_types`].
+///
+/// Selections can be combined with `|`,
+/// e.g. `FileTypes::FILES | FileTypes::DIRS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FileTypes {
+    pub files: bool,
+    pub dirs: bool,
+    pub symlinks: bool,
+    /// Everything else, e.g. sockets, FIFOs and device files
+    pub others: bool,
+}
+
+impl FileTypes {
+    pub const NONE: Self = Self {
+        files: false,
+        dirs: false,
+        symlinks: false,
+        others: false,
+    };
+    pub const FILES: Self = Self {
+        files: true,
+        ..Self::NONE
+    };
+    pub const DIRS: Self = Self {
+        dirs: true,
+        ..Self::NONE
+    };
+    pub const SYMLINKS: Self = Self {
+        symlinks: true,
+        ..Self::NONE
+    };
+    pub const OTHERS: Self = Self {
+        others: true,
+        ..Self::NONE
+    };
+    pub const ALL: Self = Self {
+        files: true,
+        dirs: true,
+        symlinks: true,
+        others: true,
+    };
+    /// Everything except directories; this is the default.
+    pub const NON_DIRS: Self = Self {
+        dirs: false,
+        ..Self::ALL
+    };
+
+    /// Whether entries of the given type are selected.
+    #[must_use]
+    pub fn matches(self, file_type: FileType) -> bool {
+        if file_type.is_dir() {
+            self.dirs
+        } else if file_type.is_file() {
+            self.files
+        } else if file_type.is_symlink() {
+            self.symlinks
+        } else {
+            self.others
+        }
+    }
+}
+
+impl Default for FileTypes {
+    fn default() -> Self {
+        Self::NON_DIRS
+    }
+}
+
+impl BitOr for FileTypes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self {
+            files: self.files || rhs.files,
+            dirs: self.dirs || rhs.dirs,
+            symlinks: self.symlinks || rhs.symlinks,
+            others: self.others || rhs.others,
+        }
+    }
+}
+
+/// The order in which the entries of each directory are walked,
+/// see [`TraversalOptions::sort`].
+///
+/// Sorting happens per directory,
+/// so the whole tree is never held in memory;
+/// one complete listing is buffered per open ancestor directory
+/// of the current entry.
+/// Entries that compare equal are ordered lexicographically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SortOrder {
+    /// The order in which the file system returns the entries,
+    /// which may differ between file systems and runs
+    #[default]
+    Unsorted,
+    /// By file name, comparing byte-wise
+    Lexicographic,
+    /// By file name, comparing sequences of digits by their numeric value,
+    /// e.g. "v2" before "v10"
+    Natural,
+    /// By modification time, oldest first
+    Modified,
+    /// By size, smallest first
+    Size,
+}
+
+impl SortOrder {
+    /// Whether sorting requires the metadata of each entry.
+    const fn needs_metadata(self) -> bool {
+        match self {
+            Self::Unsorted | Self::Lexicographic | Self::Natural => false,
+            Self::Modified | Self::Size => true,
+        }
+    }
+
+    fn compare(self, left: &RawEntry, right: &RawEntry) -> Ordering {
+        let left_name = left.path.file_name().unwrap_or_default();
+        let right_name = right.path.file_name().unwrap_or_default();
+        let ord = match self {
+            Self::Unsorted => return Ordering::Equal,
+            Self::Lexicographic => Ordering::Equal,
+            Self::Natural => {
+                natural_cmp(&left_name.to_string_lossy(), &right_name.to_string_lossy())
+            }
+            Self::Modified => {
+                let modified = |entry: &RawEntry| {
+                    entry
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.modified().ok())
+                };
+                modified(left).cmp(&modified(right))
+            }
+            Self::Size => {
+                let size = |entry: &RawEntry| entry.metadata.as_ref().map(Metadata::len);
+                size(left).cmp(&size(right))
+            }
+        };
+        ord.then_with(|| left_name.cmp(right_name))
+    }
+}
+
+/// Removes a leading sequence of ASCII digits from `chars`,
+/// and returns it without leading zeros.
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        if !(digits.is_empty() && digit == '0') {
+            digits.push(digit);
+        }
+    }
+    digits
+}
+
+/// Compares two strings in "natural" order,
+/// in which sequences of digits are compared by their numeric value.
+fn natural_cmp(left: &str, right: &str) -> Ordering {
+    let mut left_chars = left.chars().peekable();
+    let mut right_chars = right.chars().peekable();
+    loop {
+        let ord = match (left_chars.peek(), right_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(left_char), Some(right_char))
+                if left_char.is_ascii_digit() && right_char.is_ascii_digit() =>
+            {
+                let left_number = take_number(&mut left_chars);
+                let right_number = take_number(&mut right_chars);
+                left_number
+                    .len()
+                    .cmp(&right_number.len())
+                    .then_with(|| left_number.cmp(&right_number))
+            }
+            (Some(left_char), Some(right_char)) => {
+                let ord = left_char.cmp(right_char);
+                left_chars.next();
+                right_chars.next();
+                ord
+            }
+        };
+        if ord.is_ne() {
+            return ord;
+        }
+    }
+}
+
+/// Options for how to traverse a directory tree.
+///
+/// The depth of an entry is the number of path components
+/// it is located below the root,
+/// so the direct children of the root have depth 1.
+/// The root itself is never yielded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TraversalOptions {
+    pub error_policy: ErrorPolicy,
+    /// Entries with a lower depth are not yielded,
+    /// though directories are still descended into.
+    pub min_depth: usize,
+    /// Entries with a higher depth are neither yielded nor descended into;
+    /// `None` means unlimited, and `Some(0)` yields nothing,
+    /// without even reading the root.
+    pub max_depth: Option<usize>,
+    /// Whether to follow symbolic links.
+    /// Entries are then yielded with the file type of the link target,
+    /// except for broken links.
+    /// Links leading to an ancestor directory are skipped
+    /// with [`WalkOperation::FollowSymlink`].
+    pub follow_symlinks: bool,
+    /// Whether to not descend into directories
+    /// on a different file system (~= device) than the root.
+    /// This is only supported on Unix.
+    pub same_file_system: bool,
+    /// Whether to skip entries starting with a '.',
+    /// and on Windows, also those with the hidden attribute.
+    /// Hidden directories are not descended into.
+    pub skip_hidden: bool,
+    /// Which types of entries to yield
+    pub file_types: FileTypes,
+    /// The order in which to walk the entries of each directory
+    pub sort: SortOrder,
+}
+
+impl TraversalOptions {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            error_policy: ErrorPolicy::Log,
+            min_depth: 0,
+            max_depth: None,
+            follow_symlinks: false,
+            same_file_system: false,
+            skip_hidden: false,
+            file_types: FileTypes::NON_DIRS,
+            sort: SortOrder::Unsorted,
+        }
+    }
+
+    /// Whether we need [`FileId`]s of directories.
+    const fn needs_ids(&self) -> bool {
+        self.follow_symlinks || self.same_file_system
+    }
+}
+
+impl Default for TraversalOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The operation that failed for a [`SkippedEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkOperation {
+    /// Opening a directory for listing its entries
+    OpenDir,
+    /// Reading the next entry of a directory listing
+    ReadEntry,
+    /// Determining the type of an entry
+    FileType,
+    /// Reading the metadata of an entry
+    Metadata,
+    /// Following a symbolic link,
+    /// which includes detecting file system loops
+    FollowSymlink,
+}
+
+impl Display for WalkOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OpenDir => "opening directory",
+            Self::ReadEntry => "reading directory entry",
+            Self::FileType => "determining file type",
+            Self::Metadata => "reading metadata",
+            Self::FollowSymlink => "following symbolic link",
+        }
+        .fmt(f)
+    }
 }
 
+/// A path that was skipped during traversal, because of an I/O error.
+#[derive(Debug)]
+pub struct SkippedEntry {
+    /// The path of the entry, or of its directory
+    /// if the entry itself could not be read
+    pub path: PathBuf,
+    pub operation: WalkOperation,
+    pub error: std::io::Error,
+}
+
+impl Display for SkippedEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed {} '{}': {}",
+            self.operation,
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+/// Information about a finished traversal.
+#[derive(Debug, Default)]
+pub struct ScanSummary {
+    /// Entries skipped because of I/O errors,
+    /// which means that the scan is incomplete if this is not empty
+    pub skipped: Vec<SkippedEntry>,
+}
+
+impl ScanSummary {
+    /// Whether all entries could be read.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+/// An entry found during traversal.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: StdPathBuf,
+    absolute_path: StdPathBuf,
+    root: Arc<StdPath>,
+    depth: usize,
+    file_type: FileType,
+    /// Whether to follow a symbolic link when reading the metadata
+    follow_link: bool,
+    metadata: OnceLock<Metadata>,
+}
+
+impl Entry {
+    /// The path of the entry,
+    /// which is the root joined with [`Self::relative_path`].
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        Path::new(self.path.as_os_str())
+    }
+
+    /// The path of the entry relative to the root.
+    #[must_use]
+    pub fn relative_path(&self) -> &Path {
+        Path::new(
+            self.path
+                .strip_prefix(&self.root)
+                .unwrap_or(&self.path)
+                .as_os_str(),
+        )
+    }
+
+    /// The absolute path of the entry.
+    /// Symbolic links are not resolved.
+    #[must_use]
+    pub fn absolute_path(&self) -> &Path {
+        Path::new(self.absolute_path.as_os_str())
+    }
+
+    /// The number of path components this entry is located below the root,
+    /// see [`TraversalOptions`].
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The type of the entry,
+    /// which is the type of the link target
+    /// if [`TraversalOptions::follow_symlinks`] is set.
+    #[must_use]
+    pub const fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// The metadata of the entry,
+    /// which is read on first access, and then cached.
+    ///
+    /// That first access does blocking I/O,
+    /// unless the metadata was already required during traversal,
+    /// e.g. for sorting by size.
+    ///
+    /// # Errors
+    ///
+    /// If reading the metadata fails.
+    pub fn metadata(&self) -> io::Result<&Metadata> {
+        if let Some(metadata) = self.metadata.get() {
+            return Ok(metadata);
+        }
+        let metadata = if self.follow_link {
+            std::fs::metadata(&self.path)
+        } else {
+            std::fs::symlink_metadata(&self.path)
+        }?;
+        Ok(self.metadata.get_or_init(|| metadata))
+    }
+}
+
+/// Identifies a directory on the file system,
+/// for detecting loops and file system boundaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileId {
+    #[cfg(unix)]
+    dev: u64,
+    #[cfg(unix)]
+    ino: u64,
+    #[cfg(not(unix))]
+    canonical: StdPathBuf,
+}
+
+impl FileId {
+    #[cfg_attr(unix, allow(clippy::unnecessary_wraps))]
+    fn of(path: &StdPath, metadata: &std::fs::Metadata) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let _ = path;
+            Ok(Self {
+                dev: metadata.dev(),
+                ino: metadata.ino(),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            Ok(Self {
+                canonical: std::fs::canonicalize(path)?,
+            })
+        }
+    }
+
+    /// Whether both are located on the same file system.
+    /// Always `true` where this is not supported.
+    const fn same_file_system(&self, other: &Self) -> bool {
+        #[cfg(unix)]
+        {
+            self.dev == other.dev
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = other;
+            true
+        }
+    }
+}
+
+/// A failed I/O operation during traversal.
+type WalkFailure = (StdPathBuf, WalkOperation, io::Error);
+
+/// An entry of a directory listing.
+struct RawEntry {
+    path: StdPathBuf,
+    file_type: FileType,
+    /// Only set for directories, and only if [`TraversalOptions::needs_ids`]
+    id: Option<FileId>,
+    /// Only set if it was required for following a link or sorting
+    metadata: Option<Metadata>,
+}
+
+/// A directory that is to be read.
+struct PendingDir {
+    path: StdPathBuf,
+    depth: usize,
+    id: Option<FileId>,
+}
+
+/// The complete listing of a directory.
+struct Listing {
+    depth: usize,
+    id: Option<FileId>,
+    entries: std::vec::IntoIter<Result<RawEntry, WalkFailure>>,
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    if entry.file_name().as_encoded_bytes().first() == Some(&b'.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if entry
+            .metadata()
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Reads a single directory entry, following symbolic links if requested.
+///
+/// Returns `None` for entries that are skipped because they are hidden.
+fn read_entry(
+    entry: &DirEntry,
+    options: &TraversalOptions,
+) -> Result<Option<RawEntry>, WalkFailure> {
+    if options.skip_hidden && is_hidden(entry) {
+        return Ok(None);
+    }
+    let path = entry.path();
+    let link_file_type = match entry.file_type() {
+        Ok(file_type) => file_type,
+        Err(err) => return Err((path, WalkOperation::FileType, err)),
+    };
+    let (file_type, target_metadata) = if options.follow_symlinks && link_file_type.is_symlink() {
+        match std::fs::metadata(&path) {
+            Ok(followed_metadata) => (followed_metadata.file_type(), Some(followed_metadata)),
+            // A broken link, which we yield as such
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (link_file_type, None),
+            Err(err) => return Err((path, WalkOperation::FollowSymlink, err)),
+        }
+    } else {
+        (link_file_type, None)
+    };
+    let metadata = match target_metadata {
+        Some(metadata) => Some(metadata),
+        None if options.sort.needs_metadata() => match entry.metadata() {
+            Ok(metadata) => Some(metadata),
+            Err(err) => return Err((path, WalkOperation::Metadata, err)),
+        },
+        None => None,
+    };
+    let id = if options.needs_ids() && file_type.is_dir() {
+        let id_res = metadata.as_ref().map_or_else(
+            || {
+                entry
+                    .metadata()
+                    .and_then(|entry_metadata| FileId::of(&path, &entry_metadata))
+            },
+            |known_metadata| FileId::of(&path, known_metadata),
+        );
+        match id_res {
+            Ok(id) => Some(id),
+            Err(err) => return Err((path, WalkOperation::Metadata, err)),
+        }
+    } else {
+        None
+    };
+    Ok(Some(RawEntry {
+        path,
+        file_type,
+        id,
+        metadata,
+    }))
+}
+
+/// Reads the complete listing of a directory.
+///
+/// This does blocking I/O.
+fn read_listing(dir: PendingDir, options: &TraversalOptions) -> Result<Listing, WalkFailure> {
+    let id = match dir.id {
+        Some(id) => Some(id),
+        None if options.needs_ids() => {
+            match std::fs::metadata(&dir.path).and_then(|metadata| FileId::of(&dir.path, &metadata))
+            {
+                Ok(id) => Some(id),
+                Err(err) => return Err((dir.path, WalkOperation::OpenDir, err)),
+            }
+        }
+        None => None,
+    };
+    let dir_listing = match std::fs::read_dir(&dir.path) {
+        Ok(dir_listing) => dir_listing,
+        Err(err) => return Err((dir.path, WalkOperation::OpenDir, err)),
+    };
+    let mut entries: Vec<_> = dir_listing
+        .filter_map(|entry_res| match entry_res {
+            Ok(entry) => read_entry(&entry, options).transpose(),
+            Err(err) => Some(Err((dir.path.clone(), WalkOperation::ReadEntry, err))),
+        })
+        .collect();
+    if options.sort != SortOrder::Unsorted {
+        // Failures go last, in their original order
+        entries.sort_by(|left, right| match (left, right) {
+            (Ok(left_entry), Ok(right_entry)) => options.sort.compare(left_entry, right_entry),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => Ordering::Equal,
+        });
+    }
+    Ok(Listing {
+        depth: dir.depth,
+        id,
+        entries: entries.into_iter(),
+    })
+}
+
+/// What [`Walker::step`] requires the caller to do next.
+// This only lives until it is matched on, so its size is irrelevant.
+#[allow(clippy::large_enum_variant)]
+enum Step {
+    /// Hand out this entry
+    Yield(Entry),
+    /// Read this directory with [`read_listing`],
+    /// and hand the result to [`Walker::push`]
+    Read(PendingDir),
+}
+
+/// A depth-first, pre-order walker over the entries below a root directory,
+/// that does not descend into directories rejected by a filter.
+///
+/// This contains the traversal logic only;
+/// reading the directories is left to the caller,
+/// so it can be done either synchronously or asynchronously.
+struct Walker {
+    options: TraversalOptions,
+    root: Arc<StdPath>,
+    absolute_root: StdPathBuf,
+    /// The directory to read next, if any
+    pending: Option<PendingDir>,
+    /// The listings of the directories currently being walked,
+    /// from the root downwards
+    open_dirs: Vec<Listing>,
+    summary: ScanSummary,
+    /// The directories that were not descended into
+    /// because the directory filter rejected them;
+    /// only recorded if set
+    #[cfg(feature = "scan_cache")]
+    pruned: Option<Vec<StdPathBuf>>,
+}
+
+impl Walker {
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    fn new(root: &Path, options: &TraversalOptions) -> Result<Self, Error> {
+        let root_path = StdPathBuf::from(root.as_os_str());
+        let absolute_root = std::path::absolute(&root_path)
+            .map_err(|err| Error::FailedToMakeAbsolute(root_path.clone().into(), err))?;
+        Ok(Self {
+            options: *options,
+            root: root_path.as_path().into(),
+            absolute_root,
+            pending: Some(PendingDir {
+                path: root_path,
+                depth: 0,
+                id: None,
+            }),
+            open_dirs: Vec::new(),
+            summary: ScanSummary::default(),
+            #[cfg(feature = "scan_cache")]
+            pruned: None,
+        })
+    }
+
+    fn to_entry(&self, raw: RawEntry, depth: usize) -> (Entry, Option<FileId>) {
+        let absolute_path = raw.path.strip_prefix(&self.root).map_or_else(
+            |_| raw.path.clone(),
+            |relative| self.absolute_root.join(relative),
+        );
+        let metadata = OnceLock::new();
+        if let Some(known_metadata) = raw.metadata {
+            let _ = metadata.set(known_metadata);
+        }
+        let entry = Entry {
+            path: raw.path,
+            absolute_path,
+            root: Arc::clone(&self.root),
+            depth,
+            file_type: raw.file_type,
+            follow_link: self.options.follow_symlinks && !raw.file_type.is_symlink(),
+            metadata,
+        };
+        (entry, raw.id)
+    }
+
+    /// Advances the walk until either an entry is to be yielded,
+    /// or a directory has to be read.
+    ///
+    /// Directories are only descended into
+    /// if `dir_filter` accepts them.
+    fn step<D>(&mut self, dir_filter: &D) -> Option<Result<Step, Error>>
+    where
+        D: Fn(&Entry) -> PathFilterRet + ?Sized,
+    {
+        loop {
+            if let Some(dir) = self.pending.take() {
+                if self.options.max_depth.is_some_and(|max| dir.depth >= max) {
+                    // None of its entries are to be yielded
+                    continue;
+                }
+                return Some(Ok(Step::Read(dir)));
+            }
+            let listing = self.open_dirs.last_mut()?;
+            let depth = listing.depth + 1;
+            let raw = match listing.entries.next() {
+                None => {
+                    self.open_dirs.pop();
+                    continue;
+                }
+                Some(Ok(raw)) => raw,
+                Some(Err(failure)) => {
+                    if let Err(err) = self.skip(failure) {
+                        return Some(Err(err));
+                    }
+                    continue;
+                }
+            };
+            let (entry, id) = self.to_entry(raw, depth);
+            if entry.file_type.is_dir() {
+                match dir_filter(&entry) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        #[cfg(feature = "logging")]
+                        log::debug!("Pruning directory: '{}'", entry.path.display());
+                        #[cfg(feature = "scan_cache")]
+                        if let Some(pruned) = &mut self.pruned {
+                            pruned.push(entry.path);
+                        }
+                        continue;
+                    }
+                    Err(err) => return Some(Err(err.into())),
+                }
+                if self.options.max_depth.is_none_or(|max| depth < max) {
+                    match self.may_descend(&entry.path, id.as_ref()) {
+                        Ok(true) => {
+                            self.pending = Some(PendingDir {
+                                path: entry.path.clone(),
+                                depth,
+                                id,
+                            });
+                        }
+                        Ok(false) => {}
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+            }
+            if depth >= self.options.min_depth && self.options.file_types.matches(entry.file_type) {
+                return Some(Ok(Step::Yield(entry)));
+            }
+        }
+    }
+
+    /// Whether to descend into the directory at `path`,
+    /// considering file system boundaries and loops.
+    fn may_descend(&mut self, path: &StdPath, id: Option<&FileId>) -> Result<bool, Error> {
+        let Some(dir_id) = id else {
+            return Ok(true);
+        };
+        if self.options.same_file_system
+            && let Some(root_id) = self.open_dirs.first().and_then(|root| root.id.as_ref())
+            && !dir_id.same_file_system(root_id)
+        {
+            #[cfg(feature = "logging")]
+            log::debug!("Not crossing file system boundary: '{}'", path.display());
+            return Ok(false);
+        }
+        if self.options.follow_symlinks
+            && self
+                .open_dirs
+                .iter()
+                .any(|ancestor| ancestor.id.as_ref() == Some(dir_id))
+        {
+            self.skip((
+                path.to_path_buf(),
+                WalkOperation::FollowSymlink,
+                io::Error::other("File system loop: links to an ancestor directory"),
+            ))?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Adds the listing of a directory requested with [`Step::Read`].
+    fn push(&mut self, listing_res: Result<Listing, WalkFailure>) -> Result<(), Error> {
+        match listing_res {
+            Ok(listing) => {
+                self.open_dirs.push(listing);
+                Ok(())
+            }
+            Err(failure) => self.skip(failure),
+        }
+    }
+
+    /// Returns the next entry.
+    #[cfg(feature = "file_traversal")]
+    async fn next<D>(&mut self, dir_filter: &D) -> Option<Result<Entry, Error>>
+    where
+        D: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+    {
+        loop {
+            match self.step(dir_filter)? {
+                Ok(Step::Yield(entry)) => return Some(Ok(entry)),
+                Ok(Step::Read(dir)) => {
+                    let options = self.options;
+                    let listing_res =
+                        async_runtime::spawn_blocking(move || read_listing(dir, &options)).await;
+                    if let Err(err) = self.push(listing_res) {
+                        return Some(Err(err));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Takes the next skipped entry that is to be reported
+    /// as an item of a stream, see [`walk`].
+    #[cfg(feature = "file_traversal")]
+    fn take_skipped(&mut self) -> Option<SkippedEntry> {
+        match self.options.error_policy {
+            ErrorPolicy::Collect => {
+                (!self.summary.skipped.is_empty()).then(|| self.summary.skipped.remove(0))
+            }
+            ErrorPolicy::FailFast | ErrorPolicy::Log => {
+                // These were already reported, if at all
+                self.summary.skipped.clear();
+                None
+            }
+        }
+    }
+
+    /// Handles a failed I/O operation according to the error policy.
+    ///
+    /// # Errors
+    ///
+    /// If the error policy is [`ErrorPolicy::FailFast`].
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    fn skip(&mut self, (path, operation, error): WalkFailure) -> Result<(), Error> {
+        let skipped = SkippedEntry {
+            path: path.into(),
+            operation,
+            error,
+        };
+        match self.options.error_policy {
+            ErrorPolicy::FailFast => return Err(Error::Walk(skipped)),
+            ErrorPolicy::Collect => {}
+            ErrorPolicy::Log => {
+                #[cfg(feature = "logging")]
+                log::warn!("Skipping: {skipped}");
+            }
+        }
+        self.summary.skipped.push(skipped);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "file_traversal")]
 /// Searches for markup source files according to the configuration,
 /// and stores them in `collector`.
 ///
 /// # Arguments
 ///
 /// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into;
+///   use [`accept_all`] to not prune any directories
 /// - `filter` - A function that decides for each file if it should be collected
 /// - `collector` - A function that receives result paths
 ///
 /// # Errors
 ///
-/// If `root` or any of the (markup) files found through scanning `root`
-/// has no name (e.g. '.').
-/// The code-logic should prevent this from ever happening.
-pub async fn scan<F: Fn(&Path) -> PathFilterRet + Send + Sync, C: AsyncFnMut(PathBuf)>(
+/// - If any of the filters fails.
+/// - If a directory or entry could not be read,
+///   and the error policy is [`ErrorPolicy::FailFast`].
+pub async fn scan<D, F, C>(
     root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
     filter: &F,
     collector: &mut C,
-) -> Result<(), Error> {
+) -> Result<ScanSummary, Error>
+where
+    D: Fn(&Path) -> PathFilterRet + Send + Sync,
+    F: Fn(&Path) -> PathFilterRet + Send + Sync,
+    C: AsyncFnMut(PathBuf),
+{
     #[cfg(feature = "logging")]
-    log::debug!("Searching for files in directory '{root:?}' ...");
+    log::debug!("Searching for files in directory '{}' ...", root.display());
 
-    let mut dir_walker = WalkDir::new(root);
-    loop {
-        match dir_walker.next().await {
-            Some(Ok(entry)) => {
-                if let Ok(file_type) = entry.file_type().await
-                    && !file_type.is_dir()
-                {
-                    add(filter, entry.path().as_ref(), collector).await?;
-                }
-            }
-            Some(Err(_err)) => (),
-            None => break,
+    let entry_dir_filter = |entry: &Entry| dir_filter(entry.path());
+    let mut walker = Walker::new(root, options)?;
+    while let Some(entry_res) = walker.next(&entry_dir_filter).await {
+        add(filter, entry_res?.path(), collector).await?;
+    }
+
+    Ok(walker.summary)
+}
+
+#[cfg(feature = "file_traversal")]
+/// Like [`scan`], but hands [`Entry`]s instead of paths
+/// to the filters and `collector`.
+///
+/// # Arguments
+///
+/// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into
+/// - `filter` - A function that decides for each entry if it should be collected
+/// - `collector` - A function that receives result entries
+///
+/// # Errors
+///
+/// See [`scan`].
+pub async fn scan_entries<D, F, C>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+    collector: &mut C,
+) -> Result<ScanSummary, Error>
+where
+    D: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+    F: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+    C: AsyncFnMut(Entry),
+{
+    #[cfg(feature = "logging")]
+    log::debug!(
+        "Searching for entries in directory '{}' ...",
+        root.display()
+    );
+
+    let mut walker = Walker::new(root, options)?;
+    while let Some(entry_res) = walker.next(dir_filter).await {
+        let entry = entry_res?;
+        if filter(&entry)? {
+            collector(entry).await;
         }
     }
 
-    Ok(())
+    Ok(walker.summary)
 }
 
+/// Walks the entries below `root` as a stream.
+///
+/// Nothing is read before the stream is polled,
+/// and dropping it stops the traversal,
+/// so it can be combined with adapters like
+/// `take`, `filter_map` or `buffer_unordered` from [`futures::StreamExt`].
+///
+/// Entries that could not be read are handled according to the error policy:
+///
+/// - [`ErrorPolicy::FailFast`] - yields [`Error::Walk`], and ends the stream
+/// - [`ErrorPolicy::Collect`] - yields [`Error::Walk`], and continues
+/// - [`ErrorPolicy::Log`] - logs them, and continues
+///
+/// Any other error ends the stream too.
+///
+/// # Arguments
+///
+/// - `root` - The directory to walk
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into
+#[cfg(feature = "file_traversal")]
+pub fn walk<'a, D>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &'a D,
+) -> impl Stream<Item = Result<Entry, Error>> + Send + 'a
+where
+    D: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+{
+    stream::unfold(
+        Some(Walker::new(root, options)),
+        async move |walker_opt| match walker_opt? {
+            Err(err) => Some((Err(err), None)),
+            Ok(mut walker) => {
+                if let Some(skipped) = walker.take_skipped() {
+                    return Some((Err(Error::Walk(skipped)), Some(Ok(walker))));
+                }
+                match walker.next(dir_filter).await {
+                    Some(Ok(entry)) => Some((Ok(entry), Some(Ok(walker)))),
+                    Some(Err(err)) => Some((Err(err), None)),
+                    None => walker
+                        .take_skipped()
+                        .map(|skipped| (Err(Error::Walk(skipped)), Some(Ok(walker)))),
+                }
+            }
+        },
+    )
+}
+
+#[cfg(feature = "file_traversal")]
+/// The number of workers used by default for parallel traversal
+/// (see [`scan_parallel`]),
+/// which is the available parallelism of the system.
+#[must_use]
+pub fn default_workers() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+#[cfg(feature = "file_traversal")]
+/// Like [`scan`], but applies `filter` and hands the results to `collector`
+/// concurrently, in up to `workers` tasks at a time.
+///
+/// This makes sense if filtering and/or collecting is CPU-bound,
+/// e.g. because it hashes the files.
+/// The directory walk is paused while `workers` files are being processed,
+/// so the number of tasks does not grow with the size of the tree.
+/// Files are handed to `collector` in no particular order.
+///
+/// # Arguments
+///
+/// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into
+/// - `filter` - A function that decides for each file if it should be collected
+/// - `collector` - A function that receives result paths
+/// - `workers` - The maximum number of files processed concurrently;
+///   see [`default_workers`]
+///
+/// # Errors
+///
+/// - If any of the filters fails.
+/// - If a directory or entry could not be read,
+///   and the error policy is [`ErrorPolicy::FailFast`].
+///
+/// Files that are being processed at that time
+/// are still handed to `collector` (if they pass the filter).
+#[cfg_attr(
+    not(all(feature = "async-std", feature = "serde")),
+    allow(clippy::useless_conversion)
+)]
+pub async fn scan_parallel<D, F, C, Fut>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: Arc<F>,
+    collector: C,
+    workers: NonZeroUsize,
+) -> Result<ScanSummary, Error>
+where
+    D: Fn(&Path) -> PathFilterRet + Send + Sync + ?Sized,
+    F: Fn(&Path) -> PathFilterRet + Send + Sync + ?Sized + 'static,
+    C: Fn(PathBuf) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "logging")]
+    log::debug!(
+        "Searching for files in directory '{}' with {workers} workers ...",
+        root.display()
+    );
+
+    let shared_collector = Arc::new(collector);
+    let entry_dir_filter = |entry: &Entry| dir_filter(entry.path());
+    let mut walker = Walker::new(root, options)?;
+    stream::unfold(&mut walker, async |walker_ref| {
+        let entry = walker_ref.next(&entry_dir_filter).await?;
+        Some((entry, walker_ref))
+    })
+    .map_ok(|entry| {
+        let file = entry.path().to_path_buf();
+        let task_filter = Arc::clone(&filter);
+        let task_collector = Arc::clone(&shared_collector);
+        async_runtime::spawn(async move {
+            if task_filter(&file)? {
+                #[cfg(feature = "logging")]
+                log::debug!("Found file: '{}'", file.display());
+                task_collector(file.into()).await;
+            }
+            Ok::<(), Error>(())
+        })
+    })
+    .try_buffer_unordered(workers.get())
+    .try_for_each(|()| future::ready(Ok(())))
+    .await?;
+
+    Ok(walker.summary)
+}
+
+#[cfg(feature = "file_traversal")]
 /// Stores a single file in `collector`,
 /// if it is accessible
 /// and a markup source file according to the configuration.
@@ -100,12 +1293,13 @@ pub async fn add<F: Fn(&Path) -> PathFilterRet + Send + Sync, C: AsyncFnMut(Path
         return Ok(());
     }
     #[cfg(feature = "logging")]
-    log::debug!("Found file: '{file:?}'");
+    log::debug!("Found file: '{}'", file.display());
     collector(file.into()).await;
 
     Ok(())
 }
 
+#[cfg(feature = "file_traversal")]
 /// Searches for markup source files according to the configuration,
 /// and returns them as a vector.
 ///
@@ -114,23 +1308,31 @@ pub async fn add<F: Fn(&Path) -> PathFilterRet + Send + Sync, C: AsyncFnMut(Path
 /// # Arguments
 ///
 /// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into
 /// - `filter` - A function that decides for each file if it should be collected
 ///
 /// # Errors
 ///
-/// If `root` or any of the (markup) files found through scanning `root`
-/// has no name (e.g. '.').
-/// The code-logic should prevent this from ever happening.
-pub async fn find<F: Fn(&Path) -> PathFilterRet + Send + Sync>(
+/// See [`scan`].
+pub async fn find<D, F>(
     root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
     filter: &F,
-) -> Result<Vec<PathBuf>, Error> {
+) -> Result<(Vec<PathBuf>, ScanSummary), Error>
+where
+    D: Fn(&Path) -> PathFilterRet + Send + Sync,
+    F: Fn(&Path) -> PathFilterRet + Send + Sync,
+{
     let mut result = vec![];
     let mut collector = async |file: PathBuf| result.push(file);
-    scan(root, &filter, &mut collector).await?;
-    Ok(result)
+    let summary = scan(root, options, dir_filter, filter, &mut collector).await?;
+    Ok((result, summary))
 }
 
+#[cfg(feature = "file_traversal")]
 /// Searches for markup source files according to the configuration,
 /// and returns them as a vector, with the root path stripped from them.
 ///
@@ -139,20 +1341,414 @@ pub async fn find<F: Fn(&Path) -> PathFilterRet + Send + Sync>(
 /// # Arguments
 ///
 /// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into
 /// - `filter` - A function that decides for each file if it should be collected
 ///
 /// # Errors
 ///
-/// If `root` or any of the (markup) files found through scanning `root`
-/// has no name (e.g. '.').
-/// The code-logic should prevent this from ever happening.
-pub async fn find_root_stripped<F: Fn(&Path) -> PathFilterRet + Send + Sync>(
+/// See [`scan`].
+pub async fn find_root_stripped<D, F>(
     root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
     filter: &F,
-) -> Result<Vec<PathBuf>, Error> {
+) -> Result<(Vec<PathBuf>, ScanSummary), Error>
+where
+    D: Fn(&Path) -> PathFilterRet + Send + Sync,
+    F: Fn(&Path) -> PathFilterRet + Send + Sync,
+{
     let mut result = vec![];
     let mut collector =
         async |file: PathBuf| result.push(file.strip_prefix(root).unwrap_or(file.as_path()).into());
-    scan(root, &filter, &mut collector).await?;
-    Ok(result)
+    let summary = scan(root, options, dir_filter, filter, &mut collector).await?;
+    Ok((result, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a temporary directory containing the given (relative) files.
+    pub(super) fn create_tree(files: &[&str]) -> std::io::Result<tempfile::TempDir> {
+        let root = tempfile::tempdir()?;
+        for file in files {
+            let file_path = root.path().join(file);
+            if let Some(parent) = file_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(file_path, file.as_bytes())?;
+        }
+        Ok(root)
+    }
+
+    pub(super) fn strs(paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.as_path().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    pub(super) fn sorted_strs(paths: &[PathBuf]) -> Vec<String> {
+        let mut path_strs = strs(paths);
+        path_strs.sort();
+        path_strs
+    }
+
+    pub(super) const TREE: &[&str] = &["a.txt", "b.rs", "sub/c.txt", "sub/deeper/d.rs"];
+
+    pub(super) const OPTIONS: TraversalOptions = TraversalOptions {
+        error_policy: ErrorPolicy::FailFast,
+        ..TraversalOptions::new()
+    };
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_root_stripped_filtered() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let is_txt =
+            |file: &Path| -> PathFilterRet { Ok(file.extension().is_some_and(|ext| ext == "txt")) };
+        let (found, _summary) =
+            find_root_stripped(Path::new(root.path()), &OPTIONS, &accept_all, &is_txt).await?;
+        assert_eq!(sorted_strs(&found), ["a.txt", "sub/c.txt"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_pruned() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let not_deeper = |dir: &Path| -> PathFilterRet {
+            Ok(dir.file_name().is_none_or(|name| name != "deeper"))
+        };
+        let (found, _summary) =
+            find_root_stripped(Path::new(root.path()), &OPTIONS, &not_deeper, &accept_all).await?;
+        assert_eq!(sorted_strs(&found), ["a.txt", "b.rs", "sub/c.txt"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn scan_parallel_finds_all() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let found = Arc::new(std::sync::Mutex::new(vec![]));
+        let collector_found = Arc::clone(&found);
+        scan_parallel(
+            Path::new(root.path()),
+            &OPTIONS,
+            &accept_all,
+            Arc::new(accept_all),
+            move |file: PathBuf| {
+                let file_found = Arc::clone(&collector_found);
+                async move {
+                    if let Ok(mut files) = file_found.lock() {
+                        files.push(file);
+                    }
+                }
+            },
+            NonZeroUsize::MIN.saturating_add(2),
+        )
+        .await?;
+        let found_files = found.lock().map(|files| files.clone()).unwrap_or_default();
+        let (all, _summary) =
+            find(Path::new(root.path()), &OPTIONS, &accept_all, &accept_all).await?;
+        let expected = sorted_strs(&all);
+        assert_eq!(sorted_strs(&found_files), expected);
+        assert_eq!(expected.len(), TREE.len());
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_missing_root() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let missing = root.path().join("missing");
+        let fail_res = find(Path::new(&missing), &OPTIONS, &accept_all, &accept_all).await;
+        assert!(matches!(
+            fail_res,
+            Err(Error::Walk(SkippedEntry {
+                operation: WalkOperation::OpenDir,
+                ..
+            }))
+        ));
+        let collect_options = TraversalOptions {
+            error_policy: ErrorPolicy::Collect,
+            ..OPTIONS
+        };
+        let (found, summary) = find(
+            Path::new(&missing),
+            &collect_options,
+            &accept_all,
+            &accept_all,
+        )
+        .await?;
+        assert!(found.is_empty());
+        assert!(!summary.is_complete());
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    async fn find_with(root: &Path, options: TraversalOptions) -> Result<Vec<String>, Error> {
+        let (found, _summary) =
+            find_root_stripped(root, &options, &accept_all, &accept_all).await?;
+        Ok(sorted_strs(&found))
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_depth_limited() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let max_0 = TraversalOptions {
+            max_depth: Some(0),
+            ..OPTIONS
+        };
+        assert!(find_with(Path::new(root.path()), max_0).await?.is_empty());
+        let max_1 = TraversalOptions {
+            max_depth: Some(1),
+            ..OPTIONS
+        };
+        assert_eq!(
+            find_with(Path::new(root.path()), max_1).await?,
+            ["a.txt", "b.rs"]
+        );
+        let min_2 = TraversalOptions {
+            min_depth: 2,
+            ..OPTIONS
+        };
+        assert_eq!(
+            find_with(Path::new(root.path()), min_2).await?,
+            ["sub/c.txt", "sub/deeper/d.rs"]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_dirs_only() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let dirs = TraversalOptions {
+            file_types: FileTypes::DIRS,
+            ..OPTIONS
+        };
+        assert_eq!(
+            find_with(Path::new(root.path()), dirs).await?,
+            ["sub", "sub/deeper"]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_skip_hidden() -> Result<(), Error> {
+        let root = create_tree(&["a.txt", ".hidden.txt", ".git/config", "sub/.b.txt"])?;
+        let not_hidden = TraversalOptions {
+            skip_hidden: true,
+            ..OPTIONS
+        };
+        assert_eq!(
+            find_with(Path::new(root.path()), not_hidden).await?,
+            ["a.txt"]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_follow_symlinks() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        std::os::unix::fs::symlink(root.path().join("sub"), root.path().join("sub/deeper/loop"))?;
+        std::os::unix::fs::symlink(root.path().join("sub/deeper"), root.path().join("link"))?;
+        assert_eq!(
+            find_with(Path::new(root.path()), OPTIONS).await?,
+            [
+                "a.txt",
+                "b.rs",
+                "link",
+                "sub/c.txt",
+                "sub/deeper/d.rs",
+                "sub/deeper/loop"
+            ]
+        );
+        let follow = TraversalOptions {
+            follow_symlinks: true,
+            error_policy: ErrorPolicy::Collect,
+            ..OPTIONS
+        };
+        let (found, summary) =
+            find_root_stripped(Path::new(root.path()), &follow, &accept_all, &accept_all).await?;
+        assert_eq!(
+            sorted_strs(&found),
+            [
+                "a.txt",
+                "b.rs",
+                "link/d.rs",
+                "link/loop/c.txt",
+                "sub/c.txt",
+                "sub/deeper/d.rs"
+            ]
+        );
+        // Both `link/loop/deeper` and `sub/deeper/loop` lead to an ancestor
+        assert_eq!(summary.skipped.len(), 2);
+        assert!(
+            summary
+                .skipped
+                .iter()
+                .all(|skipped| skipped.operation == WalkOperation::FollowSymlink)
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn find_sorted() -> Result<(), Error> {
+        let root = create_tree(&["v10.txt", "v2.txt", "b/x", "a/y", "v1.txt"])?;
+        let sorted = |sort| TraversalOptions { sort, ..OPTIONS };
+        let (lexicographic, _summary) = find_root_stripped(
+            Path::new(root.path()),
+            &sorted(SortOrder::Lexicographic),
+            &accept_all,
+            &accept_all,
+        )
+        .await?;
+        assert_eq!(
+            strs(&lexicographic),
+            ["a/y", "b/x", "v1.txt", "v10.txt", "v2.txt"]
+        );
+        let (natural, _summary) = find_root_stripped(
+            Path::new(root.path()),
+            &sorted(SortOrder::Natural),
+            &accept_all,
+            &accept_all,
+        )
+        .await?;
+        assert_eq!(
+            strs(&natural),
+            ["a/y", "b/x", "v1.txt", "v2.txt", "v10.txt"]
+        );
+        Ok(())
+    }
+
+    macro_rules! natural_cmp_tests {
+        ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (left, right, expected) = $value;
+                assert_eq!(natural_cmp(left, right), expected);
+            }
+        )*
+        }
+    }
+
+    natural_cmp_tests! {
+        natural_cmp_equal: ("file", "file", Ordering::Equal),
+        natural_cmp_numbers: ("v2", "v10", Ordering::Less),
+        natural_cmp_leading_zeros: ("v007", "v7", Ordering::Equal),
+        natural_cmp_text_after_number: ("1b", "1a", Ordering::Greater),
+        natural_cmp_prefix: ("file", "file1", Ordering::Less),
+        natural_cmp_digit_vs_letter: ("1", "a", Ordering::Less),
+    }
+
+    #[test]
+    fn filter_combinators() -> io::Result<()> {
+        let is_rs =
+            |file: &Path| -> PathFilterRet { Ok(file.extension().is_some_and(|ext| ext == "rs")) };
+        let in_sub = |file: &Path| -> PathFilterRet { Ok(file.starts_with("sub")) };
+        let fails =
+            |_file: &Path| -> PathFilterRet { Err(io::Error::other("should not be called")) };
+
+        let sub_rs = is_rs.and(in_sub);
+        assert!(sub_rs(Path::new("sub/a.rs"))?);
+        assert!(!sub_rs(Path::new("a.rs"))?);
+        let rs_or_sub = is_rs.or(in_sub).and(accept_all);
+        assert!(rs_or_sub(Path::new("a.rs"))?);
+        assert!(rs_or_sub(Path::new("sub/a.txt"))?);
+        assert!(!rs_or_sub(Path::new("a.txt"))?);
+        let not_rs = is_rs.not();
+        assert!(not_rs(Path::new("a.txt"))?);
+        assert!(fails.not()(Path::new("a.rs")).is_err());
+
+        // Short-circuiting
+        assert!(!is_rs.and(fails)(Path::new("a.txt"))?);
+        assert!(is_rs.or(fails)(Path::new("a.rs"))?);
+
+        // Heterogeneous filters
+        let combined =
+            create_combined_filter(vec![is_rs.boxed(), in_sub.not(), accept_all.boxed()]);
+        assert!(combined(Path::new("a.rs"))?);
+        assert!(!combined(Path::new("sub/a.rs"))?);
+        let any = create_any_filter(vec![in_sub.boxed(), is_rs.boxed(), fails.boxed()]);
+        assert!(any(Path::new("sub/a.txt"))?);
+        assert!(any(Path::new("a.txt")).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn scan_entries_metadata() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let not_deeper = |dir: &Entry| -> PathFilterRet { Ok(dir.depth() < 2) };
+        let is_small = |entry: &Entry| -> PathFilterRet { Ok(entry.metadata()?.len() < 6) };
+        let mut found = vec![];
+        scan_entries(
+            Path::new(root.path()),
+            &OPTIONS,
+            &not_deeper,
+            &is_small,
+            &mut async |entry: Entry| found.push(entry),
+        )
+        .await?;
+        found.sort_by(|left, right| left.path().cmp(right.path()));
+        let relative: Vec<_> = found.iter().map(Entry::relative_path).collect();
+        assert_eq!(relative, [Path::new("a.txt"), Path::new("b.rs")]);
+        assert!(found.iter().all(|entry| entry.depth() == 1
+            && entry.file_type().is_file()
+            && entry.absolute_path().is_absolute()));
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn walk_stream() -> Result<(), Error> {
+        use futures::StreamExt;
+
+        let root = create_tree(TREE)?;
+        let sorted = TraversalOptions {
+            sort: SortOrder::Lexicographic,
+            ..OPTIONS
+        };
+        let first_two: Vec<_> = walk(Path::new(root.path()), &sorted, &|_dir: &Entry| Ok(true))
+            .take(2)
+            .try_collect()
+            .await?;
+        let relative: Vec<_> = first_two.iter().map(Entry::relative_path).collect();
+        assert_eq!(relative, [Path::new("a.txt"), Path::new("b.rs")]);
+
+        let deep: Vec<_> = walk(Path::new(root.path()), &sorted, &|_dir: &Entry| Ok(true))
+            .filter_map(async |entry_res| entry_res.ok().filter(|entry| entry.depth() > 1))
+            .collect()
+            .await;
+        assert_eq!(deep.len(), 2);
+        Ok(())
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn walk_stream_reports_skipped() -> Result<(), Error> {
+        use futures::StreamExt;
+
+        let root = create_tree(TREE)?;
+        let missing = root.path().join("missing");
+        let collect = TraversalOptions {
+            error_policy: ErrorPolicy::Collect,
+            ..OPTIONS
+        };
+        let items: Vec<_> = walk(Path::new(&missing), &collect, &|_dir: &Entry| Ok(true))
+            .collect()
+            .await;
+        assert!(matches!(items.as_slice(), [Err(Error::Walk(_))]));
+        Ok(())
+    }
 }
@@ -0,0 +1,234 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Synchronous versions of the traversal functions,
+//! for use without an async runtime.
+//!
+//! They share the filters, options and semantics
+//! with their async counterparts in the parent module.
+
+#[cfg(feature = "scan_cache")]
+use super::cache::{ChangeTracker, Changes, ScanCache};
+use super::{
+    Entry, Error, Path, PathBuf, PathFilterRet, ScanSummary, Step, TraversalOptions, Walker,
+    read_listing,
+};
+
+impl Walker {
+    /// Returns the next entry, reading directories in the current thread.
+    pub(super) fn next_blocking<D>(&mut self, dir_filter: &D) -> Option<Result<Entry, Error>>
+    where
+        D: Fn(&Entry) -> PathFilterRet + ?Sized,
+    {
+        loop {
+            match self.step(dir_filter)? {
+                Ok(Step::Yield(entry)) => return Some(Ok(entry)),
+                Ok(Step::Read(dir)) => {
+                    let listing_res = read_listing(dir, &self.options);
+                    if let Err(err) = self.push(listing_res) {
+                        return Some(Err(err));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Searches for files below `root`,
+/// and hands them to `collector`.
+///
+/// # Arguments
+///
+/// - `root` - The directory to search in
+/// - `options` - How to traverse the directory tree
+/// - `dir_filter` - A function that decides for each directory
+///   if it should be descended into;
+///   use [`super::accept_all`] to not prune any directories
+/// - `filter` - A function that decides for each file if it should be collected
+/// - `collector` - A function that receives result paths
+///
+/// # Errors
+///
+/// - If any of the filters fails.
+/// - If a directory or entry could not be read,
+///   and the error policy is [`super::ErrorPolicy::FailFast`].
+pub fn scan<D, F, C>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+    collector: &mut C,
+) -> Result<ScanSummary, Error>
+where
+    D: Fn(&Path) -> PathFilterRet + ?Sized,
+    F: Fn(&Path) -> PathFilterRet + ?Sized,
+    C: FnMut(PathBuf),
+{
+    #[cfg(feature = "logging")]
+    log::debug!("Searching for files in directory '{}' ...", root.display());
+
+    let entry_dir_filter = |entry: &Entry| dir_filter(entry.path());
+    let mut walker = Walker::new(root, options)?;
+    while let Some(entry_res) = walker.next_blocking(&entry_dir_filter) {
+        let entry = entry_res?;
+        if filter(entry.path())? {
+            #[cfg(feature = "logging")]
+            log::debug!("Found file: '{}'", entry.path().display());
+            collector(entry.path().into());
+        }
+    }
+
+    Ok(walker.summary)
+}
+
+/// Like [`scan`], but hands [`Entry`]s instead of paths
+/// to the filters and `collector`.
+///
+/// # Errors
+///
+/// See [`scan`].
+pub fn scan_entries<D, F, C>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+    collector: &mut C,
+) -> Result<ScanSummary, Error>
+where
+    D: Fn(&Entry) -> PathFilterRet + ?Sized,
+    F: Fn(&Entry) -> PathFilterRet + ?Sized,
+    C: FnMut(Entry),
+{
+    #[cfg(feature = "logging")]
+    log::debug!(
+        "Searching for entries in directory '{}' ...",
+        root.display()
+    );
+
+    let mut walker = Walker::new(root, options)?;
+    while let Some(entry_res) = walker.next_blocking(dir_filter) {
+        let entry = entry_res?;
+        if filter(&entry)? {
+            collector(entry);
+        }
+    }
+
+    Ok(walker.summary)
+}
+
+/// Searches for files below `root`,
+/// and returns them as a vector.
+///
+/// See also [`find_root_stripped`].
+///
+/// # Errors
+///
+/// See [`scan`].
+pub fn find<D, F>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+) -> Result<(Vec<PathBuf>, ScanSummary), Error>
+where
+    D: Fn(&Path) -> PathFilterRet + ?Sized,
+    F: Fn(&Path) -> PathFilterRet + ?Sized,
+{
+    let mut result = vec![];
+    let summary = scan(root, options, dir_filter, filter, &mut |file| {
+        result.push(file);
+    })?;
+    Ok((result, summary))
+}
+
+/// Searches for files below `root`,
+/// and returns them as a vector, with the root path stripped from them.
+///
+/// See also [`find`].
+///
+/// # Errors
+///
+/// See [`scan`].
+pub fn find_root_stripped<D, F>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+) -> Result<(Vec<PathBuf>, ScanSummary), Error>
+where
+    D: Fn(&Path) -> PathFilterRet + ?Sized,
+    F: Fn(&Path) -> PathFilterRet + ?Sized,
+{
+    let mut result = vec![];
+    let summary = scan(root, options, dir_filter, filter, &mut |file: PathBuf| {
+        result.push(file.strip_prefix(root).unwrap_or(file.as_path()).into());
+    })?;
+    Ok((result, summary))
+}
+
+/// Scans `root`, and reports what changed since the last scan recorded in `cache`,
+/// which gets updated to the current state.
+///
+/// `filter` is only called for files that changed.
+///
+/// # Errors
+///
+/// See [`super::cache::find_changes`].
+#[cfg(feature = "scan_cache")]
+pub fn find_changes<D, F>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+    cache: &mut ScanCache,
+) -> Result<(Changes, ScanSummary), Error>
+where
+    D: Fn(&Entry) -> PathFilterRet + ?Sized,
+    F: Fn(&Entry) -> PathFilterRet + ?Sized,
+{
+    let mut walker = Walker::new(root, options)?;
+    let mut tracker = ChangeTracker::new(cache, &mut walker);
+    while let Some(entry_res) = walker.next_blocking(dir_filter) {
+        tracker.visit(&mut walker, &entry_res?, filter)?;
+    }
+    Ok((tracker.finish(&walker), walker.summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        accept_all,
+        tests::{OPTIONS, TREE, create_tree, sorted_strs},
+    };
+    use super::*;
+
+    #[test]
+    fn find_root_stripped_filtered() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let is_rs =
+            |file: &Path| -> PathFilterRet { Ok(file.extension().is_some_and(|ext| ext == "rs")) };
+        let (found, summary) =
+            find_root_stripped(Path::new(root.path()), &OPTIONS, &accept_all, &is_rs)?;
+        assert_eq!(sorted_strs(&found), ["b.rs", "sub/deeper/d.rs"]);
+        assert!(summary.is_complete());
+        Ok(())
+    }
+
+    #[test]
+    fn scan_entries_depth() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let mut depths = vec![];
+        scan_entries(
+            Path::new(root.path()),
+            &OPTIONS,
+            &|_dir: &Entry| Ok(true),
+            &|_entry: &Entry| Ok(true),
+            &mut |entry| depths.push(entry.depth()),
+        )?;
+        depths.sort_unstable();
+        assert_eq!(depths, [1, 1, 2, 3]);
+        Ok(())
+    }
+}
@@ -0,0 +1,566 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! An on-disk cache of the last scan of a directory tree,
+//! which allows to find out what changed since then,
+//! without re-filtering (or re-hashing) unchanged files.
+//!
+//! Files are considered unchanged if their
+//! modification time, size and inode (on Unix) are unchanged.
+//! The cache does not know about the filters that were used,
+//! so use a different cache (file) for each set of filters,
+//! or [`ScanCache::clear`] it after they changed.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, Metadata},
+    io,
+    path::{Path as StdPath, PathBuf as StdPathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Entry, Error, PathBuf, PathFilterRet, WalkOperation, Walker};
+#[cfg(feature = "file_traversal")]
+use super::{Path, ScanSummary, TraversalOptions};
+
+/// What identifies the state of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+    /// Only available on Unix
+    pub inode: Option<u64>,
+}
+
+impl From<&Metadata> for FileStamp {
+    fn from(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            Some(metadata.ino())
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+        Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            inode,
+        }
+    }
+}
+
+/// What we know about a file from the last scan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedFile {
+    pub stamp: FileStamp,
+    /// Whether the file was accepted by the filter
+    pub accepted: bool,
+    /// The hash of the content of the file, if it was set by the user,
+    /// e.g. one calculated with the `hash` module;
+    /// it is kept as long as the file is unchanged
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+/// The state of all entries below a root directory from the last scan.
+///
+/// Paths are stored relative to the root,
+/// losslessly, even if they are not valid UTF-8.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanCache {
+    #[serde(with = "stored_files")]
+    files: BTreeMap<StdPathBuf, CachedFile>,
+}
+
+/// (De-)Serializes the files of a [`ScanCache`] as a list
+/// instead of a map,
+/// because many formats (e.g. JSON) only support strings as map keys,
+/// which not all paths can be represented as.
+mod stored_files {
+    use std::{collections::BTreeMap, path::PathBuf as StdPathBuf};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::CachedFile;
+
+    #[derive(Serialize)]
+    struct StoredFileRef<'a> {
+        #[serde(with = "crate::path_buf::lossless")]
+        path: &'a StdPathBuf,
+        file: &'a CachedFile,
+    }
+
+    #[derive(Deserialize)]
+    struct StoredFile {
+        #[serde(with = "crate::path_buf::lossless")]
+        path: StdPathBuf,
+        file: CachedFile,
+    }
+
+    pub fn serialize<S: Serializer>(
+        files: &BTreeMap<StdPathBuf, CachedFile>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            files
+                .iter()
+                .map(|(path, file)| StoredFileRef { path, file }),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<StdPathBuf, CachedFile>, D::Error> {
+        let stored = Vec::<StoredFile>::deserialize(deserializer)?;
+        Ok(stored
+            .into_iter()
+            .map(|StoredFile { path, file }| (path, file))
+            .collect())
+    }
+}
+
+/// What changed since the last scan.
+///
+/// Only files accepted by the filter are reported;
+/// a file that is no longer accepted counts as removed.
+/// All paths start with the scanned root, like the ones from [`super::find`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub added: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub unchanged: Vec<PathBuf>,
+}
+
+impl Changes {
+    /// Whether nothing changed since the last scan.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Returns a hash of `data` that is stable across runs and platforms
+/// (64 bit FNV-1a).
+fn stable_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl ScanCache {
+    /// Returns where to store the cache for scans of `root`,
+    /// in the per-user cache directory of the application `app_name`,
+    /// e.g. `~/.cache/<app_name>/scans/` on Linux.
+    ///
+    /// # Errors
+    ///
+    /// - If `root` could not be made absolute.
+    /// - If the platform has no cache directory.
+    pub fn location<P: AsRef<StdPath>>(app_name: &str, root: P) -> io::Result<StdPathBuf> {
+        let absolute_root = std::path::absolute(root)?;
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No cache directory"))?;
+        let file_name = format!(
+            "{:016x}.json",
+            stable_hash(absolute_root.as_os_str().as_encoded_bytes())
+        );
+        Ok(cache_dir.join(app_name).join("scans").join(file_name))
+    }
+
+    /// Loads the cache from `file`,
+    /// or returns an empty one if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// If the file exists but could not be read or parsed.
+    pub fn load<P: AsRef<StdPath>>(cache_file: P) -> Result<Self, Error> {
+        let file = cache_file.as_ref();
+        match fs::read(file) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| Error::Cache(file.to_path_buf(), err.into())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(Error::Cache(file.to_path_buf(), err)),
+        }
+    }
+
+    /// Stores the cache in `file`,
+    /// creating its parent directories if required.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be written.
+    pub fn store<P: AsRef<StdPath>>(&self, cache_file: P) -> Result<(), Error> {
+        let file = cache_file.as_ref();
+        let store = || -> io::Result<()> {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Write to a temporary file first, so the cache never ends up half-written
+            let tmp_file = file.with_extension("json.tmp");
+            fs::write(&tmp_file, serde_json::to_vec(self)?)?;
+            fs::rename(&tmp_file, file)
+        };
+        store().map_err(|err| Error::Cache(file.to_path_buf(), err))
+    }
+
+    /// Forgets everything about the last scan.
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    /// Returns what is known about a file from the last scan,
+    /// given its path relative to the root.
+    #[must_use]
+    pub fn get(&self, relative_path: &StdPath) -> Option<&CachedFile> {
+        self.files.get(relative_path)
+    }
+
+    /// Sets the hash of a file, given its path relative to the root,
+    /// to be kept for as long as it does not change.
+    ///
+    /// Returns `false` if the file is unknown.
+    pub fn set_hash(&mut self, relative_path: &StdPath, hash: String) -> bool {
+        self.files
+            .get_mut(relative_path)
+            .map(|cached| cached.hash = Some(hash))
+            .is_some()
+    }
+}
+
+/// Compares the entries of a scan with the cache,
+/// and updates the cache on the way.
+pub(super) struct ChangeTracker<'cache> {
+    cache: &'cache mut ScanCache,
+    seen: HashSet<StdPathBuf>,
+    root: StdPathBuf,
+    changes: Changes,
+}
+
+impl<'cache> ChangeTracker<'cache> {
+    /// Starts tracking the scan of `walker`,
+    /// which gets set up to record the directories it prunes.
+    pub(super) fn new(cache: &'cache mut ScanCache, walker: &mut Walker) -> Self {
+        walker.pruned = Some(Vec::new());
+        Self {
+            cache,
+            seen: HashSet::new(),
+            root: walker.root.to_path_buf(),
+            changes: Changes::default(),
+        }
+    }
+
+    /// Records an entry of the scan,
+    /// calling `filter` only if it changed since the last scan.
+    ///
+    /// If the metadata of the entry can not be read,
+    /// it is skipped according to the error policy of `walker`,
+    /// and what is known about it from the last scan is kept.
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    pub(super) fn visit<F>(
+        &mut self,
+        walker: &mut Walker,
+        entry: &Entry,
+        filter: &F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&Entry) -> PathFilterRet + ?Sized,
+    {
+        let relative_path = StdPath::new(entry.relative_path().as_os_str()).to_path_buf();
+        let stamp = match entry.metadata() {
+            Ok(metadata) => FileStamp::from(metadata),
+            Err(err) => {
+                self.seen.insert(relative_path);
+                return walker.skip((entry.path.clone(), WalkOperation::Metadata, err));
+            }
+        };
+        let prev = self.cache.files.get(&relative_path);
+        let accepted_before = prev.is_some_and(|cached| cached.accepted);
+        let (cached, unchanged) = match prev {
+            Some(cached) if cached.stamp == stamp => (cached.clone(), true),
+            Some(_) | None => {
+                let changed = CachedFile {
+                    stamp,
+                    accepted: filter(entry)?,
+                    hash: None,
+                };
+                (changed, false)
+            }
+        };
+        let path: PathBuf = StdPath::new(entry.path().as_os_str()).to_path_buf().into();
+        match (accepted_before, cached.accepted) {
+            (false, true) => self.changes.added.push(path),
+            (true, true) if unchanged => self.changes.unchanged.push(path),
+            (true, true) => self.changes.modified.push(path),
+            (true, false) => self.changes.removed.push(path),
+            (false, false) => {}
+        }
+        self.seen.insert(relative_path.clone());
+        self.cache.files.insert(relative_path, cached);
+        Ok(())
+    }
+
+    /// Removes all files from the cache that were not seen in this scan,
+    /// except for those below directories (or entries)
+    /// that `walker` skipped or pruned,
+    /// as we do not know whether they still exist.
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    pub(super) fn finish(self, walker: &Walker) -> Changes {
+        let Self {
+            cache,
+            seen,
+            root,
+            mut changes,
+        } = self;
+        let skipped = walker
+            .summary
+            .skipped
+            .iter()
+            .map(|skipped| StdPath::new(skipped.path.as_os_str()));
+        let pruned = walker.pruned.iter().flatten().map(StdPathBuf::as_path);
+        let unvisited: Vec<&StdPath> = skipped
+            .chain(pruned)
+            .filter_map(|path| path.strip_prefix(&root).ok())
+            .collect();
+        cache.files.retain(|relative_path, cached| {
+            let keep = seen.contains(relative_path)
+                || unvisited
+                    .iter()
+                    .any(|unvisited_path| relative_path.starts_with(unvisited_path));
+            if !keep && cached.accepted {
+                changes.removed.push(root.join(relative_path).into());
+            }
+            keep
+        });
+        changes
+    }
+}
+
+/// Scans `root` like [`super::scan_entries`],
+/// and reports what changed since the last scan recorded in `cache`,
+/// which gets updated to the current state.
+///
+/// `filter` is only called for files that changed.
+///
+/// # Errors
+///
+/// - If any of the filters fails.
+/// - If a directory, entry or the metadata of an entry could not be read,
+///   and the error policy is [`super::ErrorPolicy::FailFast`].
+#[cfg(feature = "file_traversal")]
+pub async fn find_changes<D, F>(
+    root: &Path,
+    options: &TraversalOptions,
+    dir_filter: &D,
+    filter: &F,
+    cache: &mut ScanCache,
+) -> Result<(Changes, ScanSummary), Error>
+where
+    D: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+    F: Fn(&Entry) -> PathFilterRet + Send + Sync + ?Sized,
+{
+    let mut walker = Walker::new(root, options)?;
+    let mut tracker = ChangeTracker::new(cache, &mut walker);
+    while let Some(entry_res) = walker.next(dir_filter).await {
+        tracker.visit(&mut walker, &entry_res?, filter)?;
+    }
+    Ok((tracker.finish(&walker), walker.summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        ErrorPolicy, Path, TraversalOptions, blocking,
+        tests::{OPTIONS, TREE, create_tree, sorted_strs},
+    };
+    use super::*;
+
+    #[test]
+    fn changes_since_last_scan() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let cache_file = root.path().join("cache/scan.json");
+        let exclude_cache =
+            |dir: &Entry| -> PathFilterRet { Ok(dir.relative_path() != Path::new("cache")) };
+        let is_txt = |entry: &Entry| -> PathFilterRet {
+            Ok(entry.path().extension().is_some_and(|ext| ext == "txt"))
+        };
+        let relative = |paths: &[PathBuf]| {
+            let stripped: Vec<PathBuf> = paths
+                .iter()
+                .filter_map(|path| {
+                    StdPath::new(path.as_os_str())
+                        .strip_prefix(root.path())
+                        .ok()
+                })
+                .map(|path| Path::new(path.as_os_str()).into())
+                .collect();
+            sorted_strs(&stripped)
+        };
+
+        let mut cache = ScanCache::load(&cache_file)?;
+        let (changes, _summary) = blocking::find_changes(
+            Path::new(root.path()),
+            &OPTIONS,
+            &exclude_cache,
+            &is_txt,
+            &mut cache,
+        )?;
+        assert_eq!(relative(&changes.added), ["a.txt", "sub/c.txt"]);
+        cache.store(&cache_file)?;
+
+        fs::remove_file(root.path().join("a.txt"))?;
+        fs::write(root.path().join("sub/c.txt"), "changed content")?;
+        fs::write(root.path().join("e.txt"), "new")?;
+        let mut reloaded = ScanCache::load(&cache_file)?;
+        assert_eq!(reloaded, cache);
+        let panicking =
+            |_entry: &Entry| -> PathFilterRet { unreachable!("Only changed files get filtered") };
+        let (changes_since, _summary) = blocking::find_changes(
+            Path::new(root.path()),
+            &OPTIONS,
+            &exclude_cache,
+            &|entry: &Entry| {
+                if entry.path().ends_with("b.rs") {
+                    panicking(entry)
+                } else {
+                    is_txt(entry)
+                }
+            },
+            &mut reloaded,
+        )?;
+        assert_eq!(relative(&changes_since.added), ["e.txt"]);
+        assert_eq!(relative(&changes_since.modified), ["sub/c.txt"]);
+        assert_eq!(relative(&changes_since.removed), ["a.txt"]);
+        // "b.rs" is unchanged, but was not accepted
+        assert!(changes_since.unchanged.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn unreadable_metadata_is_skipped() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let accept = |_entry: &Entry| -> PathFilterRet { Ok(true) };
+        let options = TraversalOptions {
+            error_policy: ErrorPolicy::Collect,
+            ..OPTIONS
+        };
+        let mut walker = Walker::new(Path::new(root.path()), &options)?;
+        let mut cache = ScanCache::default();
+        let mut tracker = ChangeTracker::new(&mut cache, &mut walker);
+        let Some(entry_res) = walker.next_blocking(&accept) else {
+            unreachable!("The tree is not empty");
+        };
+        let entry = entry_res?;
+        // The metadata is only read when required, so this makes it fail
+        fs::remove_file(entry.path())?;
+        tracker.visit(&mut walker, &entry, &accept)?;
+        assert!(tracker.finish(&walker).is_empty());
+        let operations: Vec<WalkOperation> = walker
+            .summary
+            .skipped
+            .iter()
+            .map(|skipped| skipped.operation)
+            .collect();
+        assert_eq!(operations, [WalkOperation::Metadata]);
+        Ok(())
+    }
+
+    #[test]
+    fn unvisited_dirs_are_kept() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let accept = |_entry: &Entry| -> PathFilterRet { Ok(true) };
+        let options = TraversalOptions {
+            error_policy: ErrorPolicy::Collect,
+            ..OPTIONS
+        };
+        let mut cache = ScanCache::default();
+        blocking::find_changes(
+            Path::new(root.path()),
+            &options,
+            &accept,
+            &accept,
+            &mut cache,
+        )?;
+
+        let not_deeper =
+            |dir: &Entry| -> PathFilterRet { Ok(dir.relative_path() != Path::new("sub/deeper")) };
+        let (changes, _summary) = blocking::find_changes(
+            Path::new(root.path()),
+            &options,
+            &not_deeper,
+            &accept,
+            &mut cache,
+        )?;
+        assert!(changes.removed.is_empty());
+        assert!(cache.get(StdPath::new("sub/deeper/d.rs")).is_some());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let sub = root.path().join("sub");
+            fs::set_permissions(&sub, fs::Permissions::from_mode(0o000))?;
+            // Privileged users can still read it
+            let unreadable = fs::read_dir(&sub).is_err();
+            let scan_res = blocking::find_changes(
+                Path::new(root.path()),
+                &options,
+                &accept,
+                &accept,
+                &mut cache,
+            );
+            fs::set_permissions(&sub, fs::Permissions::from_mode(0o755))?;
+            let (changes_unreadable, summary) = scan_res?;
+            if unreadable {
+                assert!(!summary.is_complete());
+                assert!(changes_unreadable.removed.is_empty());
+                assert!(cache.get(StdPath::new("sub/c.txt")).is_some());
+                assert!(cache.get(StdPath::new("sub/deeper/d.rs")).is_some());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn store_non_utf8() -> Result<(), Error> {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let root = tempfile::tempdir()?;
+        let cache_file = root.path().join("scan.json");
+        let mut cache = ScanCache::default();
+        cache.files.insert(
+            StdPath::new(OsStr::from_bytes(b"sub/caf\xe9.txt")).to_path_buf(),
+            CachedFile {
+                stamp: FileStamp {
+                    modified: None,
+                    size: 1,
+                    inode: None,
+                },
+                accepted: true,
+                hash: None,
+            },
+        );
+        cache.store(&cache_file)?;
+        assert_eq!(ScanCache::load(&cache_file)?, cache);
+        Ok(())
+    }
+
+    #[test]
+    fn stable_location() -> io::Result<()> {
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        let location = ScanCache::location("my-app", StdPath::new("/some/root"))?;
+        assert!(location.starts_with(dirs::cache_dir().unwrap_or_default().join("my-app")));
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Ready-made filters for use with the traversal functions,
+//! e.g. to be combined with [`super::create_combined_filter`].
+//!
+//! Filters that look at the content or metadata of a path
+//! reject everything that is not a regular file (after following symlinks).
+
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    ops::RangeBounds,
+    path::Path as StdPath,
+    time::SystemTime,
+};
+
+use super::{Path, PathFilter, PathFilterRet};
+
+/// How many bytes at the start of a file
+/// are considered for detecting its kind of content.
+const HEAD_LEN: u64 = 8 * 1024;
+
+/// Reads up to `len` bytes from the start of `path`,
+/// or returns `None` if it is not a regular file.
+///
+/// The file type is checked before opening,
+/// as opening e.g. a FIFO blocks until someone writes to it.
+fn read_head(path: &Path, len: u64) -> io::Result<Option<Vec<u8>>> {
+    if file_metadata(path)?.is_none() {
+        return Ok(None);
+    }
+    let file = File::open(StdPath::new(path.as_os_str()))?;
+    let mut head = Vec::new();
+    file.take(len).read_to_end(&mut head)?;
+    Ok(Some(head))
+}
+
+/// Returns the metadata of `path`,
+/// or `None` if it is not a regular file,
+/// which includes dangling symlinks.
+fn file_metadata(path: &Path) -> io::Result<Option<fs::Metadata>> {
+    match fs::metadata(StdPath::new(path.as_os_str())) {
+        Ok(metadata) => Ok(metadata.is_file().then_some(metadata)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Accepts paths with one of the given extensions,
+/// compared ASCII case-insensitively and without the leading `'.'`,
+/// e.g. `extensions(["md", "markdown"])`.
+///
+/// This does not access the file system.
+#[must_use]
+pub fn extensions<I, S>(extensions: I) -> Box<PathFilter>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let accepted_exts: Vec<String> = extensions.into_iter().map(Into::into).collect();
+    Box::new(move |path: &Path| {
+        Ok(path.extension().is_some_and(|ext| {
+            accepted_exts
+                .iter()
+                .any(|accepted| ext.eq_ignore_ascii_case(accepted.as_str()))
+        }))
+    })
+}
+
+/// Accepts files that start with the given bytes,
+/// e.g. `magic(b"%PDF-")`.
+#[must_use]
+pub fn magic(prefix: &[u8]) -> Box<PathFilter> {
+    let expected = prefix.to_vec();
+    Box::new(move |path: &Path| {
+        let head = read_head(path, expected.len() as u64)?;
+        Ok(head.is_some_and(|found| found == expected))
+    })
+}
+
+/// Accepts files of which the content is detected as one of the given MIME types,
+/// e.g. `mime_types(["image/*", "application/pdf"])`.
+/// A trailing `"/*"` matches all subtypes.
+///
+/// Detection is done with magic bytes only,
+/// so text based formats can not be told apart;
+/// see [`text_files`] for these.
+#[cfg(feature = "mime")]
+#[must_use]
+pub fn mime_types<I, S>(mime_types: I) -> Box<PathFilter>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let accepted_types: Vec<String> = mime_types.into_iter().map(Into::into).collect();
+    Box::new(move |path: &Path| {
+        let Some(head) = read_head(path, HEAD_LEN)? else {
+            return Ok(false);
+        };
+        let Some(detected) = infer::get(&head).map(|kind| kind.mime_type()) else {
+            return Ok(false);
+        };
+        Ok(accepted_types
+            .iter()
+            .any(|accepted| mime_type_matches(accepted, detected)))
+    })
+}
+
+#[cfg(feature = "mime")]
+fn mime_type_matches(accepted: &str, detected: &str) -> bool {
+    accepted.strip_suffix("/*").map_or_else(
+        || accepted.eq_ignore_ascii_case(detected),
+        |main_type| {
+            detected
+                .split_once('/')
+                .is_some_and(|(detected_main, _sub)| detected_main.eq_ignore_ascii_case(main_type))
+        },
+    )
+}
+
+/// Whether `head` looks like the start of a text file,
+/// meaning it is valid UTF-8 without NUL bytes.
+fn is_text(head: &[u8], complete: bool) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        // The head might end in the middle of a multi-byte character
+        Err(err) => !complete && err.error_len().is_none(),
+    }
+}
+
+/// Accepts files that look like text,
+/// judged by the first few KiB of their content.
+/// Empty files count as text.
+#[must_use]
+pub fn text_files() -> Box<PathFilter> {
+    Box::new(|path: &Path| {
+        let head_opt = read_head(path, HEAD_LEN)?;
+        Ok(head_opt.is_some_and(|head| is_text(&head, (head.len() as u64) < HEAD_LEN)))
+    })
+}
+
+/// Accepts regular files that do not look like text,
+/// see [`text_files`].
+#[must_use]
+pub fn binary_files() -> Box<PathFilter> {
+    Box::new(|path: &Path| {
+        let head_opt = read_head(path, HEAD_LEN)?;
+        Ok(head_opt.is_some_and(|head| !is_text(&head, (head.len() as u64) < HEAD_LEN)))
+    })
+}
+
+/// Accepts files with a size (in bytes) within `range`,
+/// e.g. `size(..1024 * 1024)`.
+#[must_use]
+pub fn size<R>(range: R) -> Box<PathFilter>
+where
+    R: RangeBounds<u64> + Send + Sync + 'static,
+{
+    Box::new(move |path: &Path| {
+        let metadata_opt = file_metadata(path)?;
+        Ok(metadata_opt.is_some_and(|metadata| range.contains(&metadata.len())))
+    })
+}
+
+/// Accepts files last modified at or after `time`.
+#[must_use]
+pub fn modified_since(time: SystemTime) -> Box<PathFilter> {
+    Box::new(move |path: &Path| {
+        let Some(metadata) = file_metadata(path)? else {
+            return Ok(false);
+        };
+        Ok(metadata.modified()? >= time)
+    })
+}
+
+/// Accepts files last modified before `time`.
+#[must_use]
+pub fn modified_before(time: SystemTime) -> Box<PathFilter> {
+    Box::new(move |path: &Path| {
+        let Some(metadata) = file_metadata(path)? else {
+            return Ok(false);
+        };
+        Ok(metadata.modified()? < time)
+    })
+}
+
+/// Returns the name of the interpreter from a shebang line,
+/// e.g. "python3" for both `#!/usr/bin/python3` and `#!/usr/bin/env -S python3 -u`.
+fn shebang_interpreter(head: &[u8]) -> Option<&str> {
+    let line = head
+        .strip_prefix(b"#!")?
+        .split(|byte| *byte == b'\n')
+        .next()?;
+    let mut args = std::str::from_utf8(line).ok()?.split_whitespace();
+    let program = args.next()?;
+    let program_name = program.rsplit('/').next()?;
+    if program_name == "env" {
+        args.find(|arg| !arg.starts_with('-'))
+    } else {
+        Some(program_name)
+    }
+}
+
+/// Accepts files with a shebang line (`#!...`) calling `interpreter`.
+///
+/// Versioned names of the interpreter are accepted too,
+/// so `shebang("python")` matches scripts starting with
+/// `#!/usr/bin/python`, `#!/usr/bin/env python3` or `#!/bin/python3.12`.
+#[must_use]
+pub fn shebang(interpreter: &str) -> Box<PathFilter> {
+    let name = interpreter.to_owned();
+    Box::new(move |path: &Path| -> PathFilterRet {
+        let Some(head) = read_head(path, 256)? else {
+            return Ok(false);
+        };
+        Ok(shebang_interpreter(&head).is_some_and(|found| {
+            found.strip_prefix(name.as_str()).is_some_and(|version| {
+                version
+                    .chars()
+                    .all(|version_char| version_char.is_ascii_digit() || version_char == '.')
+            })
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::create_combined_filter;
+    use super::*;
+
+    macro_rules! shebang_tests {
+        ($($name:ident: $head:expr => $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(shebang_interpreter($head), $expected);
+            }
+        )*
+        }
+    }
+
+    shebang_tests! {
+        shebang_plain: b"#!/bin/sh\necho" => Some("sh"),
+        shebang_env: b"#!/usr/bin/env python3\n" => Some("python3"),
+        shebang_env_split: b"#!/usr/bin/env -S python3 -u\n" => Some("python3"),
+        shebang_space: b"#! /usr/bin/perl -w" => Some("perl"),
+        shebang_missing: b"print('hi')\n" => None,
+        shebang_empty: b"#!\n" => None,
+    }
+
+    #[test]
+    fn text_detection() {
+        assert!(is_text(b"hello\n", true));
+        assert!(is_text(b"", true));
+        assert!(!is_text(b"hel\0lo", true));
+        // A truncated "é"
+        assert!(is_text(b"caf\xc3", false));
+        assert!(!is_text(b"caf\xc3", true));
+        assert!(!is_text(b"\xff\xfe", false));
+    }
+
+    #[test]
+    fn content_filters() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        for (name, content) in [
+            ("script", "#!/usr/bin/env python3\nprint('hi')\n"),
+            ("shell.sh", "#!/bin/sh\n"),
+            ("doc.PDF", "%PDF-1.7\n"),
+            ("data.bin", "\0\x01\x02"),
+        ] {
+            fs::write(root.path().join(name), content)?;
+        }
+        let file = |name: &str| root.path().join(name);
+        let accepts = |filter: &PathFilter, name: &str| filter(Path::new(&file(name)));
+
+        assert!(accepts(&*shebang("python"), "script")?);
+        assert!(!accepts(&*shebang("python"), "shell.sh")?);
+        assert!(accepts(&*extensions(["pdf"]), "doc.PDF")?);
+        assert!(!accepts(&*extensions(["pdf"]), "script")?);
+        assert!(accepts(&*magic(b"%PDF-"), "doc.PDF")?);
+        assert!(!accepts(&*magic(b"%PDF-"), "data.bin")?);
+        assert!(accepts(&*binary_files(), "data.bin")?);
+        assert!(!accepts(&*text_files(), "data.bin")?);
+        assert!(accepts(&*text_files(), "script")?);
+        assert!(!accepts(&*text_files(), ".")?);
+        assert!(accepts(&*size(..4), "data.bin")?);
+        assert!(!accepts(&*size(4..), "data.bin")?);
+        let soon = SystemTime::now() + std::time::Duration::from_mins(1);
+        assert!(accepts(&*modified_before(soon), "data.bin")?);
+        assert!(!accepts(&*modified_since(soon), "data.bin")?);
+
+        let small_scripts = create_combined_filter(vec![text_files(), size(..1024), shebang("sh")]);
+        assert!(small_scripts(Path::new(&file("shell.sh")))?);
+        assert!(!small_scripts(Path::new(&file("script")))?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn content_filters_skip_fifo() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let fifo = root.path().join("fifo");
+        let status = std::process::Command::new("mkfifo").arg(&fifo).status()?;
+        assert!(status.success());
+
+        // These would block forever if they opened the FIFO
+        assert!(!text_files()(Path::new(&fifo))?);
+        assert!(!shebang("sh")(Path::new(&fifo))?);
+        assert!(!magic(b"%PDF-")(Path::new(&fifo))?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filters_reject_dangling_symlink() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let link = root.path().join("dangling");
+        std::os::unix::fs::symlink(root.path().join("missing"), &link)?;
+        let link_path = Path::new(&link);
+
+        assert!(!size(..)(link_path)?);
+        assert!(!modified_before(SystemTime::now())(link_path)?);
+        assert!(!modified_since(SystemTime::UNIX_EPOCH)(link_path)?);
+        assert!(!text_files()(link_path)?);
+        assert!(!binary_files()(link_path)?);
+        assert!(!shebang("sh")(link_path)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "mime")]
+    #[test]
+    fn mime_type_patterns() {
+        assert!(mime_type_matches("image/*", "image/png"));
+        assert!(mime_type_matches("application/PDF", "application/pdf"));
+        assert!(!mime_type_matches("image/*", "application/pdf"));
+        assert!(!mime_type_matches("image/png", "image/jpeg"));
+    }
+}
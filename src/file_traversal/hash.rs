@@ -0,0 +1,302 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Hashes the content of files, e.g. as found by [`super::find`],
+//! to create manifests (for use as cache keys)
+//! or to find duplicate files.
+//!
+//! The available hash algorithms depend on the enabled features:
+//! `hash_blake3` and `hash_sha256`.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs::File,
+    io::{self, Read},
+    num::NonZeroUsize,
+    path::Path as StdPath,
+};
+
+use futures::{StreamExt, TryStreamExt, stream};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::async_runtime;
+
+use super::{Error, PathBuf};
+
+/// The size of the chunks in which files are read for hashing.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// The algorithm used to hash file contents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HashAlgorithm {
+    #[cfg(feature = "hash_blake3")]
+    #[default]
+    Blake3,
+    #[cfg(feature = "hash_sha256")]
+    #[cfg_attr(not(feature = "hash_blake3"), default)]
+    Sha256,
+}
+
+enum Hasher {
+    #[cfg(feature = "hash_blake3")]
+    Blake3(Box<blake3::Hasher>),
+    #[cfg(feature = "hash_sha256")]
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            #[cfg(feature = "hash_blake3")]
+            HashAlgorithm::Blake3 => Self::Blake3(Box::default()),
+            #[cfg(feature = "hash_sha256")]
+            HashAlgorithm::Sha256 => Self::Sha256(sha2::Digest::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            #[cfg(feature = "hash_blake3")]
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            #[cfg(feature = "hash_sha256")]
+            Self::Sha256(hasher) => sha2::Digest::update(hasher, data),
+        }
+    }
+
+    /// Returns the hash as lower-case hex string.
+    fn finalize(self) -> String {
+        let hash_bytes: Vec<u8> = match self {
+            #[cfg(feature = "hash_blake3")]
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            #[cfg(feature = "hash_sha256")]
+            Self::Sha256(hasher) => sha2::Digest::finalize(hasher).to_vec(),
+        };
+        hash_bytes.iter().fold(
+            String::with_capacity(hash_bytes.len() * 2),
+            |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            },
+        )
+    }
+}
+
+/// Returns the size and the hash of the content of a file.
+fn hash_file(path: &StdPath, algorithm: HashAlgorithm) -> io::Result<(u64, String)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0; CHUNK_LEN];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        let Some(chunk) = buffer.get(..read).filter(|chunk| !chunk.is_empty()) else {
+            break;
+        };
+        hasher.update(chunk);
+        size += read as u64;
+    }
+    Ok((size, hasher.finalize()))
+}
+
+/// A hashed file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ManifestEntry {
+    pub path: PathBuf,
+    /// The size of the file in bytes
+    pub size: u64,
+    /// The hash of the file content, as lower-case hex string
+    pub hash: String,
+}
+
+/// The hashes of a set of files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Manifest {
+    pub algorithm: HashAlgorithm,
+    /// Sorted by path
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// Files with the same content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DuplicateGroup {
+    /// The size of each of the files in bytes
+    pub size: u64,
+    /// The hash of the content of each of the files, as lower-case hex string
+    pub hash: String,
+    /// At least two paths, sorted
+    pub paths: Vec<PathBuf>,
+}
+
+/// Hashes the given files concurrently, using up to `workers` threads.
+///
+/// # Errors
+///
+/// If any of the files could not be read.
+pub async fn hash_files(
+    paths: Vec<PathBuf>,
+    algorithm: HashAlgorithm,
+    workers: NonZeroUsize,
+) -> Result<Manifest, Error> {
+    let mut entries: Vec<ManifestEntry> = stream::iter(paths)
+        .map(|path| async move {
+            let std_path = StdPath::new(path.as_os_str()).to_path_buf();
+            let hash_res =
+                async_runtime::spawn_blocking(move || hash_file(&std_path, algorithm)).await;
+            match hash_res {
+                Ok((size, hash)) => Ok(ManifestEntry { path, size, hash }),
+                Err(err) => Err(Error::Hash(path, err)),
+            }
+        })
+        .buffer_unordered(workers.get())
+        .try_collect()
+        .await?;
+    entries.sort_unstable_by(|left, right| left.path.cmp(&right.path));
+    Ok(Manifest { algorithm, entries })
+}
+
+/// Finds files with the same content among the given ones.
+///
+/// Files are grouped by size first,
+/// so only files that share their size with an other one get hashed;
+/// see [`hash_files`] for the remaining arguments.
+///
+/// Returns the groups of duplicates, sorted by their first path.
+///
+/// # Errors
+///
+/// If any of the files could not be read.
+pub async fn find_duplicates(
+    paths: Vec<PathBuf>,
+    algorithm: HashAlgorithm,
+    workers: NonZeroUsize,
+) -> Result<Vec<DuplicateGroup>, Error> {
+    let sized: Vec<(PathBuf, u64)> = stream::iter(paths)
+        .map(|path| async move {
+            let std_path = StdPath::new(path.as_os_str()).to_path_buf();
+            let metadata_res =
+                async_runtime::spawn_blocking(move || std::fs::metadata(std_path)).await;
+            match metadata_res {
+                Ok(metadata) => Ok((path, metadata.len())),
+                Err(err) => Err(Error::Hash(path, err)),
+            }
+        })
+        .buffer_unordered(workers.get())
+        .try_collect()
+        .await?;
+
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in sized {
+        by_size.entry(size).or_default().push(path);
+    }
+    let candidates = by_size
+        .into_values()
+        .filter(|same_size| same_size.len() > 1)
+        .flatten()
+        .collect();
+
+    let manifest = hash_files(candidates, algorithm, workers).await?;
+    let mut by_hash: HashMap<(u64, String), Vec<PathBuf>> = HashMap::new();
+    for entry in manifest.entries {
+        by_hash
+            .entry((entry.size, entry.hash))
+            .or_default()
+            .push(entry.path);
+    }
+    // The paths are already sorted, as they come from the manifest
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_key, same_hash)| same_hash.len() > 1)
+        .map(|((size, hash), same_hash)| DuplicateGroup {
+            size,
+            hash,
+            paths: same_hash,
+        })
+        .collect();
+    groups.sort_unstable_by(|left, right| left.paths.first().cmp(&right.paths.first()));
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        accept_all, default_workers, find,
+        tests::{OPTIONS, create_tree},
+    };
+    use super::*;
+    use crate::file_traversal::Path;
+
+    #[cfg(feature = "hash_blake3")]
+    #[test]
+    fn blake3_empty() {
+        assert_eq!(
+            Hasher::new(HashAlgorithm::Blake3).finalize(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[cfg(feature = "hash_sha256")]
+    #[test]
+    fn sha256_empty() {
+        assert_eq!(
+            Hasher::new(HashAlgorithm::Sha256).finalize(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicates() -> Result<(), Error> {
+        // The content of each file is its path,
+        // so we create duplicates by writing over some of them
+        let root = create_tree(&["a.txt", "b.txt", "sub/c.txt", "sub/d.txt", "e.bin"])?;
+        for dup in ["b.txt", "sub/c.txt"] {
+            std::fs::write(root.path().join(dup), "a.txt")?;
+        }
+        std::fs::write(root.path().join("sub/d.txt"), "x.txt")?;
+        let (found, _summary) =
+            find(Path::new(root.path()), &OPTIONS, &accept_all, &accept_all).await?;
+
+        let manifest =
+            hash_files(found.clone(), HashAlgorithm::default(), default_workers()).await?;
+        assert_eq!(manifest.entries.len(), 5);
+        assert!(
+            manifest
+                .entries
+                .windows(2)
+                .all(|pair| matches!(pair, [left, right] if left.path < right.path))
+        );
+
+        let groups = find_duplicates(found, HashAlgorithm::default(), default_workers()).await?;
+        let group_paths: Vec<Vec<_>> = groups
+            .iter()
+            .map(|group| {
+                group
+                    .paths
+                    .iter()
+                    .filter_map(|path| {
+                        StdPath::new(path.as_os_str())
+                            .strip_prefix(root.path())
+                            .ok()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            group_paths,
+            [[
+                StdPath::new("a.txt"),
+                StdPath::new("b.txt"),
+                StdPath::new("sub/c.txt")
+            ]]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,611 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Watches a directory tree for changes,
+//! e.g. to rebuild after an initial [`super::find`].
+//!
+//! Changes are reported in debounced batches,
+//! and filtered with the same options and filters as a traversal.
+//! This uses the native notification mechanism of the platform,
+//! e.g. inotify on Linux.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path as StdPath, PathBuf as StdPathBuf},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "file_traversal")]
+use futures::{Stream, stream};
+use notify::{
+    Config, RecommendedWatcher, RecursiveMode, Watcher as _,
+    event::{EventKind, ModifyKind, RenameMode},
+};
+
+#[cfg(feature = "file_traversal")]
+use crate::async_runtime;
+
+use super::{Error, Path, PathBuf, PathFilterRet, TraversalOptions};
+
+/// The default time to wait for further changes,
+/// before reporting a batch of them.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// How many debounce periods a batch may collect changes at most,
+/// before it is reported even though changes keep coming in.
+///
+/// This ensures continuous activity (e.g. a long running build)
+/// does not delay the reporting indefinitely.
+pub const MAX_BATCH_AGE_FACTOR: u32 = 10;
+
+/// What happened to a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+    /// The path was moved here from an other path below the root.
+    Renamed {
+        from: PathBuf,
+    },
+}
+
+/// A change to a single path below the watched root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The changed path, starting with the watched root,
+    /// like the paths reported by [`super::scan`]
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+}
+
+/// Like [`ChangeKind`], but with the absolute paths we get from `notify`.
+#[derive(Debug, Clone)]
+enum RawKind {
+    Created,
+    Modified,
+    Removed,
+    Renamed { from: StdPathBuf },
+}
+
+/// Collects the changes of a single debounce period,
+/// merging multiple changes of the same path into one.
+#[derive(Default)]
+struct Batch {
+    indices: HashMap<StdPathBuf, usize>,
+    changes: Vec<Option<(StdPathBuf, RawKind)>>,
+    /// The sources of renames we have not yet seen the target of,
+    /// by tracker ID, together with what we recorded for them before
+    rename_sources: HashMap<usize, (StdPathBuf, Option<RawKind>)>,
+}
+
+impl Batch {
+    fn is_empty(&self) -> bool {
+        self.indices.is_empty() && self.rename_sources.is_empty()
+    }
+
+    fn take(&mut self, path: &StdPath) -> Option<RawKind> {
+        let index = self.indices.remove(path)?;
+        self.changes
+            .get_mut(index)
+            .and_then(Option::take)
+            .map(|(_path, kind)| kind)
+    }
+
+    fn record(&mut self, path: StdPathBuf, kind: RawKind) {
+        let prev = self.take(&path);
+        self.merge(path, prev, kind);
+    }
+
+    fn merge(&mut self, path: StdPathBuf, prev: Option<RawKind>, kind: RawKind) {
+        let merged = match (prev, kind) {
+            (Some(RawKind::Created), RawKind::Removed) => None,
+            (Some(RawKind::Created), RawKind::Created | RawKind::Modified) => {
+                Some(RawKind::Created)
+            }
+            (Some(RawKind::Removed | RawKind::Modified), RawKind::Created | RawKind::Modified) => {
+                Some(RawKind::Modified)
+            }
+            (Some(RawKind::Renamed { from }), RawKind::Created | RawKind::Modified) => {
+                Some(RawKind::Renamed { from })
+            }
+            (
+                Some(RawKind::Renamed { from }),
+                next @ (RawKind::Removed | RawKind::Renamed { .. }),
+            ) => {
+                self.record(from, RawKind::Removed);
+                Some(next)
+            }
+            (
+                None | Some(RawKind::Created | RawKind::Removed | RawKind::Modified),
+                next @ (RawKind::Removed | RawKind::Renamed { .. }),
+            )
+            | (None, next @ (RawKind::Created | RawKind::Modified)) => Some(next),
+        };
+        if let Some(kind) = merged {
+            self.indices.insert(path.clone(), self.changes.len());
+            self.changes.push(Some((path, kind)));
+        }
+    }
+
+    /// Records the move of a path,
+    /// given what was recorded for its source before.
+    fn rename(&mut self, from: StdPathBuf, from_prev: Option<RawKind>, to: StdPathBuf) {
+        let kind = match from_prev {
+            Some(RawKind::Created) => RawKind::Created,
+            Some(RawKind::Renamed { from: original }) if original == to => RawKind::Modified,
+            Some(RawKind::Renamed { from: original }) => RawKind::Renamed { from: original },
+            None | Some(RawKind::Modified | RawKind::Removed) => RawKind::Renamed { from },
+        };
+        self.record(to, kind);
+    }
+
+    fn add(&mut self, event: notify::Event) {
+        let tracker = event.tracker();
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            EventKind::Create(_) => paths.for_each(|path| self.record(path, RawKind::Created)),
+            EventKind::Remove(_) => paths.for_each(|path| self.record(path, RawKind::Removed)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for path in paths {
+                    if let Some(id) = tracker {
+                        let prev = self.take(&path);
+                        self.rename_sources.insert(id, (path, prev));
+                    } else {
+                        self.record(path, RawKind::Removed);
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                let source = tracker.and_then(|id| self.rename_sources.remove(&id));
+                for path in paths {
+                    if let Some((from, from_prev)) = &source {
+                        self.rename(from.clone(), from_prev.clone(), path);
+                    } else {
+                        self.record(path, RawKind::Created);
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                // With a tracker, this was already handled by `From` and `To`
+                if tracker.is_none()
+                    && let (Some(from), Some(to)) = (paths.next(), paths.next())
+                {
+                    let from_prev = self.take(&from);
+                    self.rename(from, from_prev, to);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Any | RenameMode::Other)) => {
+                for path in paths {
+                    let kind = if fs::symlink_metadata(&path).is_ok() {
+                        RawKind::Created
+                    } else {
+                        RawKind::Removed
+                    };
+                    self.record(path, kind);
+                }
+            }
+            EventKind::Modify(
+                ModifyKind::Any | ModifyKind::Data(_) | ModifyKind::Metadata(_) | ModifyKind::Other,
+            ) => paths.for_each(|path| self.record(path, RawKind::Modified)),
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => {}
+        }
+    }
+
+    /// Returns the merged changes, in the order of their last change.
+    fn finish(mut self) -> Vec<(StdPathBuf, RawKind)> {
+        // Whatever was moved out of the watched tree is gone for us
+        for (_id, (path, prev)) in std::mem::take(&mut self.rename_sources) {
+            self.merge(path, prev, RawKind::Removed);
+        }
+        self.changes.into_iter().flatten().collect()
+    }
+}
+
+/// Decides which changes get reported,
+/// and how their paths look like.
+struct Selector<D, F> {
+    options: TraversalOptions,
+    root: StdPathBuf,
+    absolute_root: StdPathBuf,
+    dir_filter: D,
+    filter: F,
+}
+
+impl<D, F> Selector<D, F>
+where
+    D: Fn(&Path) -> PathFilterRet,
+    F: Fn(&Path) -> PathFilterRet,
+{
+    /// Returns the path to report for `path`,
+    /// or `None` if it is filtered out.
+    fn select(&self, path: &StdPath) -> io::Result<Option<StdPathBuf>> {
+        let Ok(relative) = path.strip_prefix(&self.absolute_root) else {
+            return Ok(None);
+        };
+        let depth = relative.components().count();
+        if depth < self.options.min_depth.max(1)
+            || self.options.max_depth.is_some_and(|max| depth > max)
+        {
+            return Ok(None);
+        }
+        if self.options.skip_hidden
+            && relative
+                .components()
+                .any(|component| component.as_os_str().as_encoded_bytes().first() == Some(&b'.'))
+        {
+            return Ok(None);
+        }
+        let metadata_res = if self.options.follow_symlinks {
+            fs::metadata(path)
+        } else {
+            fs::symlink_metadata(path)
+        };
+        // We can not know the type of paths that do not exist (anymore)
+        if metadata_res.is_ok_and(|metadata| !self.options.file_types.matches(metadata.file_type()))
+        {
+            return Ok(None);
+        }
+        let reported = self.root.join(relative);
+        for dir in reported.ancestors().skip(1).take(depth - 1) {
+            if !(self.dir_filter)(Path::new(dir.as_os_str()))? {
+                return Ok(None);
+            }
+        }
+        if !(self.filter)(Path::new(reported.as_os_str()))? {
+            return Ok(None);
+        }
+        Ok(Some(reported))
+    }
+
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    fn to_change(&self, path: &StdPath, raw_kind: RawKind) -> io::Result<Option<Change>> {
+        let selected = self.select(path)?;
+        let (changed_path, kind) = match raw_kind {
+            RawKind::Created => (selected, ChangeKind::Created),
+            RawKind::Modified => (selected, ChangeKind::Modified),
+            RawKind::Removed => (selected, ChangeKind::Removed),
+            RawKind::Renamed { from } => match (self.select(&from)?, selected) {
+                (Some(from_path), Some(to_path)) => (
+                    Some(to_path),
+                    ChangeKind::Renamed {
+                        from: from_path.into(),
+                    },
+                ),
+                (None, to_path) => (to_path, ChangeKind::Created),
+                (Some(from_path), None) => (Some(from_path), ChangeKind::Removed),
+            },
+        };
+        Ok(changed_path.map(|changed| Change {
+            path: changed.into(),
+            kind,
+        }))
+    }
+}
+
+/// Watches a directory tree for changes.
+///
+/// Changes are reported in batches,
+/// each one after no further changes happened for the debounce period,
+/// or at the latest [`MAX_BATCH_AGE_FACTOR`] debounce periods
+/// after its first change.
+/// Multiple changes of the same path within a batch are merged into one,
+/// e.g. a file that was created and then modified is reported as created,
+/// while one that was created and removed again is not reported at all.
+///
+/// Watching stops when this is dropped.
+pub struct Watcher {
+    // Only kept for it to not stop watching
+    _watcher: RecommendedWatcher,
+    changes: mpsc::Receiver<Result<Vec<Change>, Error>>,
+}
+
+impl Watcher {
+    /// Starts watching `root` for changes.
+    ///
+    /// Of the `options`, the depth limits, `skip_hidden`, `file_types`
+    /// and `follow_symlinks` are applied;
+    /// `file_types` can only be checked for paths that still exist.
+    ///
+    /// # Arguments
+    ///
+    /// - `root` - The directory to watch
+    /// - `options` - Which changes to report, like for a traversal
+    /// - `debounce` - For how long no further changes have to happen,
+    ///   before a batch of changes is reported,
+    ///   see [`DEFAULT_DEBOUNCE`]
+    /// - `dir_filter` - A function that decides for each directory
+    ///   if changes below it are reported;
+    ///   use [`super::accept_all`] to not exclude any directories
+    /// - `filter` - A function that decides for each changed path
+    ///   if it is reported
+    ///
+    /// # Errors
+    ///
+    /// - If `root` could not be made absolute.
+    /// - If the watch could not be set up, e.g. because `root` does not exist.
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    pub fn new<D, F>(
+        root: &Path,
+        options: &TraversalOptions,
+        debounce: Duration,
+        dir_filter: D,
+        filter: F,
+    ) -> Result<Self, Error>
+    where
+        D: Fn(&Path) -> PathFilterRet + Send + 'static,
+        F: Fn(&Path) -> PathFilterRet + Send + 'static,
+    {
+        let root_path = StdPathBuf::from(root.as_os_str());
+        let absolute_root = std::path::absolute(&root_path)
+            .map_err(|err| Error::FailedToMakeAbsolute(root_path.clone().into(), err))?;
+        let (events_tx, events_rx) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(
+            events_tx,
+            Config::default().with_follow_symlinks(options.follow_symlinks),
+        )?;
+        watcher.watch(&absolute_root, RecursiveMode::Recursive)?;
+
+        let selector = Selector {
+            options: *options,
+            root: root_path,
+            absolute_root,
+            dir_filter,
+            filter,
+        };
+        let (changes_tx, changes_rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("file-watch-debounce".to_owned())
+            .spawn(move || debounce_loop(&events_rx, &changes_tx, &selector, debounce))?;
+
+        Ok(Self {
+            _watcher: watcher,
+            changes: changes_rx,
+        })
+    }
+
+    /// Waits for the next batch of changes.
+    ///
+    /// Errors do not stop the watching;
+    /// they are reported in between the batches.
+    ///
+    /// Returns `None` if watching stopped,
+    /// which only happens if the watch failed irrecoverably.
+    #[must_use]
+    pub fn recv(&self) -> Option<Result<Vec<Change>, Error>> {
+        self.changes.recv().ok()
+    }
+
+    /// Like [`Self::recv`], but waits at most for `timeout`.
+    ///
+    /// Returns `None` if no changes happened within that time,
+    /// or if watching stopped.
+    #[must_use]
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<Vec<Change>, Error>> {
+        self.changes.recv_timeout(timeout).ok()
+    }
+
+    /// Turns this into a stream of batches of changes,
+    /// see [`Self::recv`].
+    #[cfg(feature = "file_traversal")]
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<Change>, Error>> + Send {
+        stream::unfold(self, async |watcher| {
+            async_runtime::spawn_blocking(move || {
+                let batch_res = watcher.recv()?;
+                Some((batch_res, watcher))
+            })
+            .await
+        })
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Result<Vec<Change>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+/// Collects the raw events into debounced, filtered batches of changes,
+/// until either side of it hangs up.
+fn debounce_loop<D, F>(
+    events: &mpsc::Receiver<notify::Result<notify::Event>>,
+    changes: &mpsc::Sender<Result<Vec<Change>, Error>>,
+    selector: &Selector<D, F>,
+    debounce: Duration,
+) where
+    D: Fn(&Path) -> PathFilterRet,
+    F: Fn(&Path) -> PathFilterRet,
+{
+    let max_age = debounce.saturating_mul(MAX_BATCH_AGE_FACTOR);
+    let mut batch = Batch::default();
+    let mut batch_start = Instant::now();
+    let mut watching = true;
+    while watching {
+        let event_res = if batch.is_empty() {
+            events
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+        } else {
+            let remaining = max_age.saturating_sub(batch_start.elapsed());
+            events.recv_timeout(debounce.min(remaining))
+        };
+        match event_res {
+            Ok(Ok(event)) => {
+                if batch.is_empty() {
+                    batch_start = Instant::now();
+                }
+                batch.add(event);
+                if batch_start.elapsed() < max_age {
+                    continue;
+                }
+            }
+            Ok(Err(err)) => {
+                if changes.send(Err(err.into())).is_err() {
+                    return;
+                }
+                continue;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => watching = false,
+        }
+        let mut selected = Vec::new();
+        for (path, kind) in std::mem::take(&mut batch).finish() {
+            match selector.to_change(&path, kind) {
+                Ok(Some(change)) => selected.push(change),
+                Ok(None) => {}
+                Err(err) => {
+                    if changes.send(Err(err.into())).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        if !selected.is_empty() && changes.send(Ok(selected)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        accept_all,
+        tests::{OPTIONS, TREE, create_tree},
+    };
+    use super::*;
+
+    const DEBOUNCE: Duration = Duration::from_millis(100);
+
+    fn next_batch(watcher: &Watcher) -> Result<Vec<Change>, Error> {
+        watcher
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap_or_else(|| Err(io::Error::from(io::ErrorKind::TimedOut).into()))
+    }
+
+    #[test]
+    fn watch_debounced() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let watcher = Watcher::new(
+            Path::new(root.path()),
+            &OPTIONS,
+            DEBOUNCE,
+            accept_all,
+            accept_all,
+        )?;
+        let new_file = root.path().join("new.txt");
+        fs::write(&new_file, "1")?;
+        fs::write(&new_file, "2")?;
+        fs::remove_file(root.path().join("a.txt"))?;
+
+        let mut batch = next_batch(&watcher)?;
+        batch.sort_by(|left, right| left.path.cmp(&right.path));
+        assert_eq!(
+            batch,
+            [
+                Change {
+                    path: Path::new(&root.path().join("a.txt")).into(),
+                    kind: ChangeKind::Removed,
+                },
+                Change {
+                    path: Path::new(&new_file).into(),
+                    kind: ChangeKind::Created,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn watch_filtered_rename() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let is_rs =
+            |file: &Path| -> PathFilterRet { Ok(file.extension().is_some_and(|ext| ext == "rs")) };
+        let not_deeper = |dir: &Path| -> PathFilterRet { Ok(!dir.ends_with("deeper")) };
+        let watcher = Watcher::new(
+            Path::new(root.path()),
+            &OPTIONS,
+            DEBOUNCE,
+            not_deeper,
+            is_rs,
+        )?;
+        fs::write(root.path().join("sub/deeper/ignored.rs"), "")?;
+        fs::write(root.path().join("ignored.txt"), "")?;
+        fs::rename(root.path().join("b.rs"), root.path().join("c.rs"))?;
+
+        let batch = next_batch(&watcher)?;
+        assert_eq!(
+            batch,
+            [Change {
+                path: Path::new(&root.path().join("c.rs")).into(),
+                kind: ChangeKind::Renamed {
+                    from: Path::new(&root.path().join("b.rs")).into(),
+                },
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn watch_max_batch_age() -> Result<(), Error> {
+        let root = create_tree(TREE)?;
+        let watcher = Watcher::new(
+            Path::new(root.path()),
+            &OPTIONS,
+            DEBOUNCE,
+            accept_all,
+            accept_all,
+        )?;
+        let max_age = DEBOUNCE * MAX_BATCH_AGE_FACTOR;
+        let start = Instant::now();
+        let file = root.path().join("a.txt");
+        // Keep changing the file more often than the debounce period,
+        // for longer than the maximum batch age
+        while start.elapsed() < max_age * 3 {
+            fs::write(&file, start.elapsed().as_millis().to_string())?;
+            if watcher.recv_timeout(DEBOUNCE / 4).is_some() {
+                assert!(start.elapsed() < max_age * 2);
+                return Ok(());
+            }
+        }
+        panic!("No batch was reported while changes kept coming in");
+    }
+
+    #[cfg(feature = "file_traversal")]
+    #[tokio::test]
+    async fn watch_stream() -> Result<(), Error> {
+        use futures::StreamExt;
+
+        let root = create_tree(TREE)?;
+        let watcher = Watcher::new(
+            Path::new(root.path()),
+            &OPTIONS,
+            DEBOUNCE,
+            accept_all,
+            accept_all,
+        )?;
+        fs::write(root.path().join("a.txt"), "changed")?;
+
+        let mut changes = Box::pin(watcher.into_stream());
+        let batch = changes.next().await.transpose()?;
+        assert_eq!(
+            batch,
+            Some(vec![Change {
+                path: Path::new(&root.path().join("a.txt")).into(),
+                kind: ChangeKind::Modified,
+            }])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,423 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Expands glob patterns in CLI arguments,
+//! for when the shell did not do it,
+//! e.g. on Windows or when arguments are passed on verbatim by CI runners.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path as StdPath, PathBuf as StdPathBuf},
+};
+
+use thiserror::Error;
+
+use crate::StreamIdent;
+use crate::ignore_path::{IgnoreRules, into_absolute};
+#[cfg(feature = "serde")]
+use crate::path_buf::PathBuf;
+#[cfg(feature = "async-std")]
+use async_std::path::Path;
+#[cfg(all(feature = "async-std", not(feature = "serde")))]
+use async_std::path::PathBuf;
+#[cfg(not(feature = "async-std"))]
+use std::path::Path;
+#[cfg(not(any(feature = "async-std", feature = "serde")))]
+use std::path::PathBuf;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Glob pattern '{0}' did not match any files")]
+    NoMatches(String),
+
+    #[error("Invalid glob pattern '{0}': unclosed character class")]
+    UnclosedCharClass(String),
+
+    #[error("Failed to expand glob pattern '{0}': {1}")]
+    IO(String, std::io::Error),
+}
+
+/// Whether `arg` contains any glob meta characters,
+/// and thus should be expanded.
+#[must_use]
+pub fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Matches a single path component (file name) against a pattern component,
+/// supporting `*`, `?` and character classes like `[a-z]` or `[!0-9]`.
+///
+/// Returns `None` if the pattern contains an unclosed character class.
+fn matches_component(pattern: &[char], name: &[char]) -> Option<bool> {
+    match pattern.split_first() {
+        None => Some(name.is_empty()),
+        Some(('*', rest)) => {
+            for skip in 0..=name.len() {
+                if matches_component(rest, name.get(skip..)?)? {
+                    return Some(true);
+                }
+            }
+            Some(false)
+        }
+        Some(('?', rest)) => match name.split_first() {
+            Some((_, name_rest)) => matches_component(rest, name_rest),
+            None => Some(false),
+        },
+        Some(('[', class)) => {
+            let (negated, class_body) = match class.split_first() {
+                Some(('!' | '^', negated_class)) => (true, negated_class),
+                Some(_) | None => (false, class),
+            };
+            // A ']' right at the start is part of the class
+            let end = class_body
+                .iter()
+                .skip(1)
+                .position(|class_char| *class_char == ']')?
+                + 1;
+            let (Some(set), Some(rest)) = (class_body.get(..end), class_body.get(end + 1..)) else {
+                return None;
+            };
+            let Some((name_char, name_rest)) = name.split_first() else {
+                return Some(false);
+            };
+            let mut in_set = false;
+            let mut idx = 0;
+            while let Some(set_char) = set.get(idx) {
+                if let (Some('-'), Some(range_end)) = (set.get(idx + 1), set.get(idx + 2)) {
+                    in_set |= (set_char..=range_end).contains(&name_char);
+                    idx += 3;
+                } else {
+                    in_set |= set_char == name_char;
+                    idx += 1;
+                }
+            }
+            if in_set == negated {
+                return Some(false);
+            }
+            matches_component(rest, name_rest)
+        }
+        Some((pattern_char, rest)) => match name.split_first() {
+            Some((name_char, name_rest)) if name_char == pattern_char => {
+                matches_component(rest, name_rest)
+            }
+            Some(_) | None => Some(false),
+        },
+    }
+}
+
+/// Expands a single glob pattern.
+struct Expansion<'a> {
+    pattern: &'a str,
+    ignore: Option<&'a IgnoreRules>,
+    matches: Vec<StdPathBuf>,
+}
+
+impl Expansion<'_> {
+    fn is_ignored(&self, path: &StdPath, is_dir: bool) -> Result<bool, Error> {
+        let Some(rules) = self.ignore else {
+            return Ok(false);
+        };
+        let abs_path = into_absolute(path).map_err(|err| self.io_err(err))?;
+        let decision = if is_dir {
+            rules.matching_dir(Path::new(abs_path.as_os_str()))
+        } else {
+            rules.matching(Path::new(abs_path.as_os_str()))
+        };
+        Ok(decision.is_some_and(|rule_match| rule_match.ignores()))
+    }
+
+    fn io_err(&self, err: std::io::Error) -> Error {
+        Error::IO(self.pattern.to_owned(), err)
+    }
+
+    /// Collects all files below `dir` that match the pattern `components`.
+    fn visit(&mut self, dir: &StdPath, components: &[Vec<char>]) -> Result<(), Error> {
+        let Some((component, rest)) = components.split_first() else {
+            if !dir.is_dir() && !self.is_ignored(dir, false)? {
+                self.matches.push(dir.to_path_buf());
+            }
+            return Ok(());
+        };
+        let is_globstar = component.as_slice() == ['*', '*'];
+        if is_globstar {
+            // Matching no directory at all
+            self.visit(dir, rest)?;
+        }
+        let read_dir = if dir.as_os_str().is_empty() {
+            fs::read_dir(".")
+        } else {
+            fs::read_dir(dir)
+        };
+        // Like shells, we silently skip what we can not read
+        let Ok(entries) = read_dir else {
+            return Ok(());
+        };
+        let mut children = Vec::new();
+        for entry_res in entries {
+            let entry = entry_res.map_err(|err| self.io_err(err))?;
+            let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+            // Like shells, we only match hidden files if explicitly asked for
+            if name.first() == Some(&'.') && component.first() != Some(&'.') {
+                continue;
+            }
+            children.push((entry, name));
+        }
+        for (entry, name) in children {
+            let matched = is_globstar
+                || matches_component(component, &name)
+                    .ok_or_else(|| Error::UnclosedCharClass(self.pattern.to_owned()))?;
+            if !matched {
+                continue;
+            }
+            let path = dir.join(entry.file_name());
+            match (path.is_dir(), rest.is_empty()) {
+                (true, _) if is_globstar => {
+                    // Like bash, we do not recurse into symlinked directories,
+                    // as they might lead into a loop
+                    let is_real_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+                    let remaining = if is_real_dir { components } else { rest };
+                    if !self.is_ignored(&path, true)? {
+                        self.visit(&path, remaining)?;
+                    }
+                }
+                (true, false) => {
+                    if !self.is_ignored(&path, true)? {
+                        self.visit(&path, rest)?;
+                    }
+                }
+                // Only files get matched there
+                (_, true) => self.visit(&path, rest)?,
+                (false, false) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Expands a single glob pattern into the files matching it,
+/// sorted and without duplicates.
+fn expand_pattern(pattern: &str, ignore: Option<&IgnoreRules>) -> Result<Vec<StdPathBuf>, Error> {
+    let pattern_path = StdPath::new(pattern);
+    let mut base = StdPathBuf::new();
+    let mut components = Vec::new();
+    for component in pattern_path.components() {
+        match component {
+            Component::Normal(name)
+                if !components.is_empty() || is_glob(&name.to_string_lossy()) =>
+            {
+                components.push(name.to_string_lossy().chars().collect());
+            }
+            Component::Prefix(_)
+            | Component::RootDir
+            | Component::CurDir
+            | Component::ParentDir
+            | Component::Normal(_) => {
+                if components.is_empty() {
+                    base.push(component);
+                } else {
+                    components.push(component.as_os_str().to_string_lossy().chars().collect());
+                }
+            }
+        }
+    }
+    let mut expansion = Expansion {
+        pattern,
+        ignore,
+        matches: Vec::new(),
+    };
+    expansion.visit(&base, &components)?;
+    let mut matches = expansion.matches;
+    matches.sort();
+    matches.dedup();
+    Ok(matches)
+}
+
+/// Expands glob patterns in CLI arguments into streams.
+///
+/// Arguments without glob meta characters (`*`, `?` and `[`)
+/// are passed on as they are, including `"-"` for stdin/stdout.
+/// Patterns are matched per path component,
+/// with `*` not matching the path separator,
+/// and `**` as a whole component matching any number of directories.
+/// Like in shells, hidden files only match
+/// if the pattern component starts with a `.` too.
+///
+/// Each pattern expands to the (non-directory) files it matches, sorted.
+/// A pattern without matches is taken literally if such a path exists,
+/// as the meta characters might be part of a file name.
+/// Paths appearing multiple times are only returned the first time.
+/// If `ignore` is given, files and directories ignored by it are skipped;
+/// this is only applied to expanded patterns, not to literal paths.
+///
+/// # Errors
+///
+/// - If a pattern does not match any file.
+/// - If a pattern is invalid.
+/// - If reading a directory entry failed.
+#[cfg_attr(
+    not(any(feature = "async-std", feature = "serde")),
+    allow(clippy::useless_conversion)
+)]
+pub fn expand_args<I, S>(
+    args: I,
+    r#in: bool,
+    ignore: Option<&IgnoreRules>,
+) -> Result<Vec<StreamIdent>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut seen = HashSet::new();
+    let mut idents = Vec::new();
+    for arg in args {
+        let arg_str = arg.as_ref();
+        let paths = if is_glob(arg_str) {
+            let matches = expand_pattern(arg_str, ignore)?;
+            if matches.is_empty() {
+                // The meta characters might be part of an actual file name,
+                // e.g. "report[1].txt"
+                if !StdPath::new(arg_str).exists() {
+                    return Err(Error::NoMatches(arg_str.to_owned()));
+                }
+                vec![StdPathBuf::from(arg_str)]
+            } else {
+                matches
+            }
+        } else {
+            vec![StdPathBuf::from(arg_str)]
+        };
+        for path in paths {
+            if seen.insert(path.clone()) {
+                idents.push(StreamIdent::from((PathBuf::from(path), r#in)));
+            }
+        }
+    }
+    Ok(idents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ignore_path::IgnorePath;
+
+    macro_rules! component_tests {
+        ($($name:ident: $pattern:expr, $file_name:expr => $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let pattern: Vec<char> = $pattern.chars().collect();
+                let file_name: Vec<char> = $file_name.chars().collect();
+                assert_eq!(matches_component(&pattern, &file_name), $expected);
+            }
+        )*
+        }
+    }
+
+    component_tests! {
+        component_literal: "a.rs", "a.rs" => Some(true),
+        component_star: "*.rs", "main.rs" => Some(true),
+        component_star_empty: "*.rs", ".rs" => Some(true),
+        component_star_mismatch: "*.rs", "main.rs.bak" => Some(false),
+        component_question: "?.rs", "a.rs" => Some(true),
+        component_question_mismatch: "?.rs", "ab.rs" => Some(false),
+        component_class: "[ab].rs", "b.rs" => Some(true),
+        component_class_range: "[0-9]x", "7x" => Some(true),
+        component_class_negated: "[!0-9]x", "7x" => Some(false),
+        component_class_bracket: "[]]", "]" => Some(true),
+        component_class_unclosed: "[ab", "a" => None,
+    }
+
+    fn create_files(root: &StdPath, files: &[&str]) -> std::io::Result<()> {
+        for file in files {
+            let file_path = root.join(file);
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(file_path, file)?;
+        }
+        Ok(())
+    }
+
+    fn rel_strs(root: &StdPath, idents: &[StreamIdent]) -> Vec<String> {
+        idents
+            .iter()
+            .map(|ident| match ident {
+                StreamIdent::Path(path, _in) => StdPath::new(path.as_os_str())
+                    .strip_prefix(root)
+                    .map_or_else(
+                        |_| path.display().to_string(),
+                        |rel| rel.display().to_string(),
+                    ),
+                StreamIdent::StdIn | StreamIdent::StdOut => ident.description().into_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expand() -> Result<(), Box<dyn std::error::Error>> {
+        let root = tempfile::tempdir()?;
+        create_files(
+            root.path(),
+            &[
+                "src/main.rs",
+                "src/lib.rs",
+                "src/a/b.rs",
+                "src/a/c.txt",
+                "src/.hidden.rs",
+                "target/x.rs",
+            ],
+        )?;
+        let base = root.path().display().to_string();
+        let args = [
+            format!("{base}/src/*.rs"),
+            format!("{base}/src/**/*.rs"),
+            "-".to_owned(),
+        ];
+        let idents = expand_args(&args, true, None)?;
+        assert_eq!(
+            rel_strs(root.path(), &idents),
+            ["src/lib.rs", "src/main.rs", "src/a/b.rs", "stdin"]
+        );
+
+        let ignore = IgnoreRules::from(vec![IgnorePath::Regex(regex::Regex::new("/src/a+/$")?)]);
+        let all_rs = [format!("{base}/**/*.rs")];
+        let idents = expand_args(&all_rs, true, Some(&ignore))?;
+        assert_eq!(
+            rel_strs(root.path(), &idents),
+            ["src/lib.rs", "src/main.rs", "target/x.rs"]
+        );
+
+        create_files(root.path(), &["report[1].txt"])?;
+        let literal = [format!("{base}/report[1].txt")];
+        let idents = expand_args(&literal, true, None)?;
+        assert_eq!(rel_strs(root.path(), &idents), ["report[1].txt"]);
+
+        let none = [format!("{base}/*.md")];
+        assert!(matches!(
+            expand_args(&none, true, None),
+            Err(Error::NoMatches(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expand_symlink_loop() -> Result<(), Box<dyn std::error::Error>> {
+        let root = tempfile::tempdir()?;
+        create_files(root.path(), &["a/x.rs"])?;
+        std::os::unix::fs::symlink("..", root.path().join("a/up"))?;
+        let base = root.path().display().to_string();
+
+        let all_rs = [format!("{base}/**/*.rs")];
+        let idents = expand_args(&all_rs, true, None)?;
+        assert_eq!(rel_strs(root.path(), &idents), ["a/x.rs"]);
+
+        // Explicitly named symlinked directories are followed
+        let through_link = [format!("{base}/**/up/a/*.rs")];
+        let idents = expand_args(&through_link, true, None)?;
+        assert_eq!(rel_strs(root.path(), &idents), ["a/up/a/x.rs"]);
+        Ok(())
+    }
+}
@@ -3,25 +3,30 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt::Display;
 
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+#[cfg(feature = "unicode_normalization")]
+use unicode_normalization::UnicodeNormalization;
 use wildmatch::WildMatch;
 
-#[cfg(feature = "file_traversal")]
+#[cfg(feature = "file_traversal_sync")]
 use crate::file_traversal::PathFilterRet;
-#[cfg(all(feature = "async", feature = "serde"))]
+#[cfg(feature = "serde")]
 use crate::path_buf::PathBuf;
-#[cfg(feature = "async")]
+#[cfg(feature = "async-std")]
 use async_std::path::Path;
-#[cfg(all(feature = "async", not(feature = "serde")))]
+#[cfg(all(feature = "async-std", not(feature = "serde")))]
 use async_std::path::PathBuf;
-#[cfg(not(feature = "async"))]
-use std::path::{Path, PathBuf};
+#[cfg(not(feature = "async-std"))]
+use std::path::Path;
+#[cfg(not(any(feature = "async-std", feature = "serde")))]
+use std::path::PathBuf;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -33,6 +38,105 @@ pub enum Error {
 Do not know how to use i."
     )]
     UnknownPathType(PathBuf),
+
+    #[error("Ignore pattern is empty (syntax: '{0}')")]
+    EmptyPattern(PatternSyntax),
+
+    #[error("Ignore pattern '{0}' is not a valid regex: {1}")]
+    InvalidRegex(String, #[source] regex::Error),
+
+    #[error("Ignore pattern '{0}' is not a valid gitignore pattern: {1}")]
+    InvalidGitignorePattern(String, GitignorePatternError),
+
+    #[error("Failed to read ignore rules file '{0:?}': {1:?}")]
+    FailedToReadRulesFile(PathBuf, std::io::Error),
+
+    #[error("Invalid ignore rule in '{0:?}', line {1}: {2}")]
+    InvalidRuleInFile(PathBuf, usize, Box<Self>),
+}
+
+/// The reason why a pattern is not a valid gitignore pattern,
+/// as supported by [`GitignorePattern`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitignorePatternError {
+    #[error("unclosed character class (missing ']')")]
+    UnclosedCharClass,
+
+    #[error("trailing, unescaped backslash")]
+    TrailingBackslash,
+}
+
+/// The syntax an ignore pattern is written in,
+/// as denoted by its prefix (e.g. `"glob:"`) when parsed with [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternSyntax {
+    /// `"path:"` - An existing file (-> [`IgnorePath::Whole`])
+    /// or directory (-> [`IgnorePath::Prefix`]).
+    Path,
+    /// `"prefix:"` - A path prefix, which does not need to exist
+    /// (-> [`IgnorePath::Prefix`]).
+    Prefix,
+    /// `"glob:"` - A glob supporting `*` and `?` (-> [`IgnorePath::Glob`]).
+    Glob,
+    /// `"re:"` - A regular expression (-> [`IgnorePath::Regex`]).
+    Regex,
+    /// `"gitignore:"` - A pattern as used in `.gitignore` files
+    /// (-> [`IgnorePath::Gitignore`]).
+    Gitignore,
+}
+
+impl PatternSyntax {
+    pub const ALL: [Self; 5] = [
+        Self::Path,
+        Self::Prefix,
+        Self::Glob,
+        Self::Regex,
+        Self::Gitignore,
+    ];
+
+    /// The prefix denoting this syntax, including the trailing `':'`.
+    #[must_use]
+    pub const fn prefix(self) -> &'static str {
+        match self {
+            Self::Path => "path:",
+            Self::Prefix => "prefix:",
+            Self::Glob => "glob:",
+            Self::Regex => "re:",
+            Self::Gitignore => "gitignore:",
+        }
+    }
+
+    /// Splits a known syntax prefix off of `pattern`.
+    ///
+    /// Returns `None` as syntax if `pattern` does not start with a known prefix,
+    /// in which case the pattern is returned unchanged.
+    #[must_use]
+    pub fn split(pattern: &str) -> (Option<Self>, &str) {
+        for syntax in Self::ALL {
+            if let Some(stripped) = pattern.strip_prefix(syntax.prefix()) {
+                return (Some(syntax), stripped);
+            }
+        }
+        (None, pattern)
+    }
+
+    /// Guesses the syntax of a pattern without a prefix:
+    /// If it contains any of the glob meta-characters `'*'` or `'?'`,
+    /// it is considered a glob, otherwise a path.
+    #[must_use]
+    pub fn detect(pattern: &str) -> Self {
+        if pattern.contains(['*', '?']) {
+            Self::Glob
+        } else {
+            Self::Path
+        }
+    }
+}
+
+impl Display for PatternSyntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.prefix().fmt(f)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +152,8 @@ pub enum IgnorePath {
     /// Matches [paths matching a regex.
     #[cfg_attr(feature = "serde", serde(with = "serde_regex"))]
     Regex(Regex),
+    /// Matches paths matching a pattern as used in `.gitignore` files.
+    Gitignore(GitignorePattern),
 }
 
 impl IgnorePath {
@@ -60,38 +166,533 @@ impl IgnorePath {
             Self::Regex(regex) => regex
                 .captures(abs_path.to_string_lossy().as_ref())
                 .is_some(),
+            Self::Gitignore(pattern) => pattern.matches(abs_path),
         }
     }
 
-    #[cfg(feature = "file_traversal")]
+    /// Whether this pattern re-includes paths it matches,
+    /// instead of ignoring them.
+    /// This is only the case for negated gitignore patterns (e.g. `"!keep.txt"`).
+    /// See [`IgnoreRules::matching`].
+    #[must_use]
+    pub const fn is_negation(&self) -> bool {
+        match self {
+            Self::Gitignore(pattern) => pattern.is_negation(),
+            Self::Whole(_) | Self::Prefix(_) | Self::Glob(_) | Self::Regex(_) => false,
+        }
+    }
+
+    /// Returns a version of this pattern that matches paths
+    /// folded according to `options`
+    /// (see [`MatchOptions::fold_path`]).
+    ///
+    /// # Errors
+    ///
+    /// If a regex could not be re-built,
+    /// e.g. because it grows too big when made case-insensitive.
+    #[cfg_attr(
+        not(all(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
+    pub fn folded(&self, options: &MatchOptions) -> Result<Self, Error> {
+        if options.is_identity() {
+            return Ok(self.clone());
+        }
+        Ok(match self {
+            Self::Whole(path) => Self::Whole(options.fold_path(path.as_ref()).into_owned().into()),
+            Self::Prefix(path) => {
+                Self::Prefix(options.fold_path(path.as_ref()).into_owned().into())
+            }
+            Self::Glob(glob) => Self::Glob(WildMatch::new(&options.fold(&glob.pattern()))),
+            Self::Regex(regex) => {
+                // NOTE We can not fold the regex itself,
+                //      because lower-casing would change the meaning
+                //      of escapes like `\D` or `\S`.
+                let pattern = options
+                    .with_case_insensitive(false)
+                    .fold(regex.as_str())
+                    .into_owned();
+                Self::Regex(
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(options.case_insensitive)
+                        .build()
+                        .map_err(|err| Error::InvalidRegex(pattern, err))?,
+                )
+            }
+            Self::Gitignore(pattern) => {
+                let folded_base = pattern.base().map(|base| {
+                    std::path::PathBuf::from(options.fold(&base.to_string_lossy()).into_owned())
+                });
+                Self::Gitignore(GitignorePattern::new(
+                    &options.fold(pattern.pattern()),
+                    folded_base.as_deref(),
+                )?)
+            }
+        })
+    }
+
+    /// Creates a filter that rejects all files matched by `ignore_paths`.
+    /// See [`IgnoreRules::create_filter`].
+    #[cfg(feature = "file_traversal_sync")]
     #[must_use]
     pub fn create_filter(
         ignore_paths: Vec<Self>,
     ) -> Box<dyn Fn(&Path) -> PathFilterRet + Send + Sync> {
+        IgnoreRules::from(ignore_paths).create_filter()
+    }
+}
+
+/// Where an [`IgnoreRule`] was defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleSource {
+    /// The file the rule was read from
+    pub file: PathBuf,
+    /// The line within `file` (starting at 1)
+    pub line: usize,
+}
+
+impl Display for RuleSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)
+    }
+}
+
+/// An [`IgnorePath`], plus where it was defined.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IgnoreRule {
+    pub pattern: IgnorePath,
+    /// `None` if the rule was not read from a file,
+    /// e.g. if it was supplied as a CLI argument.
+    pub source: Option<RuleSource>,
+}
+
+impl From<IgnorePath> for IgnoreRule {
+    fn from(pattern: IgnorePath) -> Self {
+        Self {
+            pattern,
+            source: None,
+        }
+    }
+}
+
+impl Display for IgnoreRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.pattern.fmt(f)
+    }
+}
+
+/// The rule that decided whether a path is ignored,
+/// as returned by [`IgnoreRules::matching`].
+#[derive(Debug, Clone, Copy)]
+pub struct RuleMatch<'a> {
+    /// The index of the rule within its [`IgnoreRules`]
+    pub index: usize,
+    pub rule: &'a IgnoreRule,
+}
+
+impl RuleMatch<'_> {
+    /// Whether the matched rule ignores the path;
+    /// `false` means, it re-includes it.
+    #[must_use]
+    pub const fn ignores(&self) -> bool {
+        !self.rule.pattern.is_negation()
+    }
+}
+
+impl Display for RuleMatch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rule #{} '{}'", self.index, self.rule)?;
+        if let Some(source) = &self.rule.source {
+            write!(f, " (from {source})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A Unicode normalization form.
+#[cfg(feature = "unicode_normalization")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Normalization {
+    /// Canonical composition, as commonly used on Linux and Windows
+    Nfc,
+    /// Canonical decomposition, as used by macOS file-systems
+    Nfd,
+}
+
+/// How paths are compared with ignore patterns.
+///
+/// Both the patterns and the paths are transformed ("folded")
+/// according to these options before being matched,
+/// which allows e.g. to scan trees synced from case-insensitive
+/// or macOS file-systems consistently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MatchOptions {
+    /// Whether to ignore case, by lower-casing both patterns and paths.
+    ///
+    /// To make only a single regex pattern case-insensitive,
+    /// use its inline flag instead: `"re:(?i)..."`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub case_insensitive: bool,
+    /// To which Unicode normalization form both patterns and paths
+    /// are converted before matching, if any.
+    #[cfg(feature = "unicode_normalization")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normalization: Option<Normalization>,
+}
+
+impl MatchOptions {
+    #[must_use]
+    pub const fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    #[cfg(feature = "unicode_normalization")]
+    #[must_use]
+    pub const fn with_normalization(mut self, normalization: Option<Normalization>) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Whether these options leave patterns and paths unchanged.
+    #[must_use]
+    pub const fn is_identity(&self) -> bool {
+        #[cfg(feature = "unicode_normalization")]
+        if self.normalization.is_some() {
+            return false;
+        }
+        !self.case_insensitive
+    }
+
+    /// Transforms a string according to these options.
+    #[must_use]
+    pub fn fold<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        #[cfg(feature = "unicode_normalization")]
+        let normalized: Cow<'a, str> = match self.normalization {
+            None => Cow::Borrowed(text),
+            Some(Normalization::Nfc) => Cow::Owned(text.nfc().collect()),
+            Some(Normalization::Nfd) => Cow::Owned(text.nfd().collect()),
+        };
+        #[cfg(not(feature = "unicode_normalization"))]
+        let normalized = Cow::Borrowed(text);
+        if self.case_insensitive {
+            Cow::Owned(normalized.to_lowercase())
+        } else {
+            normalized
+        }
+    }
+
+    /// Transforms a path according to these options.
+    ///
+    /// NOTE Non UTF-8 paths are converted lossily,
+    ///      unless [`Self::is_identity`].
+    #[must_use]
+    pub fn fold_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        if self.is_identity() {
+            return Cow::Borrowed(path);
+        }
+        let folded = self.fold(&path.to_string_lossy()).into_owned();
+        #[cfg_attr(not(feature = "async-std"), allow(clippy::useless_conversion))]
+        Cow::Owned(std::path::PathBuf::from(folded).into())
+    }
+}
+
+/// An ordered list of ignore rules.
+///
+/// Like in `.gitignore` files, the last rule matching a path decides
+/// whether it is ignored, which allows negated patterns
+/// to re-include paths ignored by earlier rules.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "IgnoreRulesSpec", into = "IgnoreRulesSpec")
+)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+    options: MatchOptions,
+    /// The patterns of `rules`, folded according to `options`
+    folded_patterns: Vec<IgnorePath>,
+}
+
+/// The serialized form of [`IgnoreRules`].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct IgnoreRulesSpec {
+    rules: Vec<IgnoreRule>,
+    #[serde(default)]
+    options: MatchOptions,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<IgnoreRulesSpec> for IgnoreRules {
+    type Error = Error;
+
+    fn try_from(spec: IgnoreRulesSpec) -> Result<Self, Self::Error> {
+        let mut rules = Self::from_iter(spec.rules);
+        rules.set_options(spec.options)?;
+        Ok(rules)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<IgnoreRules> for IgnoreRulesSpec {
+    fn from(rules: IgnoreRules) -> Self {
+        Self {
+            rules: rules.rules,
+            options: rules.options,
+        }
+    }
+}
+
+impl IgnoreRules {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            options: MatchOptions {
+                case_insensitive: false,
+                #[cfg(feature = "unicode_normalization")]
+                normalization: None,
+            },
+            folded_patterns: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn rules(&self) -> &[IgnoreRule] {
+        &self.rules
+    }
+
+    #[must_use]
+    pub const fn options(&self) -> &MatchOptions {
+        &self.options
+    }
+
+    /// Sets how paths are compared with the patterns of all rules,
+    /// including the ones added later on.
+    ///
+    /// # Errors
+    ///
+    /// If a pattern could not be adjusted to the new options,
+    /// e.g. because a regex grows too big when made case-insensitive.
+    pub fn set_options(&mut self, options: MatchOptions) -> Result<(), Error> {
+        self.folded_patterns = self
+            .rules
+            .iter()
+            .map(|rule| rule.pattern.folded(&options))
+            .collect::<Result<_, _>>()?;
+        self.options = options;
+        Ok(())
+    }
+
+    /// Adds a rule at the end.
+    ///
+    /// # Errors
+    ///
+    /// If the pattern could not be adjusted to the [`MatchOptions`] in use.
+    pub fn push<R: Into<IgnoreRule>>(&mut self, new_rule: R) -> Result<(), Error> {
+        let rule = new_rule.into();
+        self.folded_patterns
+            .push(rule.pattern.folded(&self.options)?);
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Adds all patterns from a file in `.gitignore` syntax.
+    /// Anchored patterns are relative to the directory containing the file.
+    ///
+    /// # Errors
+    ///
+    /// - if the file could not be read
+    /// - if any of the lines is not a valid gitignore pattern
+    pub fn add_gitignore_file<P: AsRef<Path>>(&mut self, file_path: P) -> Result<(), Error> {
+        let file = file_path.as_ref();
+        let abs_file =
+            into_absolute(file).map_err(|err| Error::FailedToCanonicalize(file.into(), err))?;
+        let content = std::fs::read_to_string(&abs_file)
+            .map_err(|err| Error::FailedToReadRulesFile(file.into(), err))?;
+        let base = abs_file.parent();
+        for (line_idx, line) in content.lines().enumerate() {
+            let Some(pattern) = gitignore_line_pattern(line) else {
+                continue;
+            };
+            let line_num = line_idx + 1;
+            GitignorePattern::new(pattern, base)
+                .and_then(|gitignore_pattern| {
+                    self.push(IgnoreRule {
+                        pattern: IgnorePath::Gitignore(gitignore_pattern),
+                        source: Some(RuleSource {
+                            file: file.into(),
+                            line: line_num,
+                        }),
+                    })
+                })
+                .map_err(|err| Error::InvalidRuleInFile(file.into(), line_num, Box::new(err)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the rule deciding whether `abs_path` is ignored,
+    /// which is the last one matching it,
+    /// or `None` if no rule matches.
+    ///
+    /// Use [`RuleMatch::ignores`] to check whether the path is ignored,
+    /// or [`Self::is_ignored`] if you do not need to know the rule.
+    #[must_use]
+    pub fn matching(&self, abs_path: &Path) -> Option<RuleMatch<'_>> {
+        let folded_path = self.options.fold_path(abs_path);
+        self.folded_patterns
+            .iter()
+            .zip(&self.rules)
+            .enumerate()
+            .rev()
+            .find(|(_index, (pattern, _rule))| pattern.matches(&folded_path))
+            .map(|(index, (_pattern, rule))| RuleMatch { index, rule })
+    }
+
+    #[must_use]
+    pub fn is_ignored(&self, abs_path: &Path) -> bool {
+        self.matching(abs_path)
+            .is_some_and(|rule_match| rule_match.ignores())
+    }
+
+    /// Like [`Self::matching`], but for a directory,
+    /// which makes directory-only patterns like `"build/"` match it.
+    #[must_use]
+    pub fn matching_dir(&self, abs_dir: &Path) -> Option<RuleMatch<'_>> {
+        let mut dir_str = abs_dir.as_os_str().to_owned();
+        dir_str.push(std::path::MAIN_SEPARATOR_STR);
+        self.matching(Path::new(&dir_str))
+    }
+
+    /// Creates a filter that rejects all files ignored by these rules.
+    #[cfg(feature = "file_traversal_sync")]
+    #[must_use]
+    pub fn create_filter(self) -> Box<dyn Fn(&Path) -> PathFilterRet + Send + Sync> {
+        self.create_filter_with_reporter(|_file, _decision| {})
+    }
+
+    /// Creates a filter that rejects all directories ignored by these rules.
+    /// Use it as `dir_filter` in [`crate::file_traversal::scan`],
+    /// to not descend into ignored directories at all.
+    ///
+    /// NOTE Like with git, files within an ignored directory
+    ///      can not be re-included by negated patterns then.
+    #[cfg(feature = "file_traversal_sync")]
+    #[must_use]
+    pub fn create_dir_filter(self) -> Box<dyn Fn(&Path) -> PathFilterRet + Send + Sync> {
+        Box::new(move |dir: &Path| {
+            let abs_dir = into_absolute_lenient(dir)?;
+            let decision = self.matching_dir(abs_dir.as_ref());
+            let ignored = decision.is_some_and(|rule_match| rule_match.ignores());
+            #[cfg(feature = "logging")]
+            if let Some(rule_match) = decision.filter(|_| ignored) {
+                log::debug!(
+                    "Ignoring directory '{}', because of {rule_match}.",
+                    dir.display()
+                );
+            }
+            Ok(!ignored)
+        })
+    }
+
+    /// Creates a filter that rejects all files ignored by these rules,
+    /// and logs the decision - including the deciding rule -
+    /// for every file it is applied to.
+    ///
+    /// This is meant to answer the question:
+    /// "Why is my file (not) processed?"
+    #[cfg(all(feature = "file_traversal_sync", feature = "logging"))]
+    #[must_use]
+    pub fn create_explaining_filter(self) -> Box<dyn Fn(&Path) -> PathFilterRet + Send + Sync> {
+        self.create_filter_with_reporter(|file, decision| match decision {
+            Some(rule_match) if rule_match.ignores() => {
+                log::info!("Ignoring '{}', because of {rule_match}.", file.display());
+            }
+            Some(rule_match) => {
+                log::info!("Keeping '{}', because of {rule_match}.", file.display());
+            }
+            None => log::info!("Keeping '{}', because no rule matches.", file.display()),
+        })
+    }
+
+    /// Creates a filter that rejects all files ignored by these rules,
+    /// and calls `reporter` with the deciding rule
+    /// for every file it is applied to.
+    #[cfg(feature = "file_traversal_sync")]
+    #[must_use]
+    pub fn create_filter_with_reporter<R>(
+        self,
+        reporter: R,
+    ) -> Box<dyn Fn(&Path) -> PathFilterRet + Send + Sync>
+    where
+        R: Fn(&Path, Option<RuleMatch<'_>>) + Send + Sync + 'static,
+    {
         Box::new(move |file: &Path| {
-            let abs_path = into_absolute(file)?;
-            if ignore_paths
-                .iter()
-                .any(|ignore_path| ignore_path.matches(abs_path.as_ref()))
-            {
-                #[cfg(feature = "logging")]
+            let abs_path = into_absolute_lenient(file)?;
+            let decision = self.matching(abs_path.as_ref());
+            let ignored = decision.is_some_and(|rule_match| rule_match.ignores());
+            #[cfg(feature = "logging")]
+            if let Some(rule_match) = decision.filter(|_| ignored) {
                 log::debug!(
-                    "Ignoring file '{}', because it is in the ignore paths list.",
+                    "Ignoring file '{}', because of {rule_match}.",
                     file.display()
                 );
-                return Ok(false);
             }
-            Ok(true)
+            reporter(file, decision);
+            Ok(!ignored)
         })
     }
 }
 
+impl From<Vec<IgnorePath>> for IgnoreRules {
+    fn from(ignore_paths: Vec<IgnorePath>) -> Self {
+        ignore_paths.into_iter().map(IgnoreRule::from).collect()
+    }
+}
+
+/// Creates rules with the default [`MatchOptions`].
+impl FromIterator<IgnoreRule> for IgnoreRules {
+    fn from_iter<I: IntoIterator<Item = IgnoreRule>>(iter: I) -> Self {
+        let rules: Vec<IgnoreRule> = iter.into_iter().collect();
+        Self {
+            folded_patterns: rules.iter().map(|rule| rule.pattern.clone()).collect(),
+            rules,
+            ..Self::new()
+        }
+    }
+}
+
+/// Extracts the pattern from a line of a `.gitignore` file,
+/// or returns `None` for empty and comment lines.
+fn gitignore_line_pattern(line: &str) -> Option<&str> {
+    if line.starts_with('#') {
+        return None;
+    }
+    // trailing spaces are ignored, unless escaped
+    let mut pattern = line.trim_end();
+    if pattern.ends_with('\\') && pattern.len() < line.len() {
+        pattern = line.get(..=pattern.len()).unwrap_or(pattern);
+    }
+    if pattern.is_empty() {
+        None
+    } else {
+        Some(pattern)
+    }
+}
+
 impl Display for IgnorePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Whole(path) | Self::Prefix(path) => path.display().fmt(f),
             Self::Glob(glob) => glob.fmt(f),
             Self::Regex(regex) => regex.fmt(f),
+            Self::Gitignore(pattern) => pattern.fmt(f),
         }
     }
 }
@@ -102,7 +703,10 @@ impl TryFrom<&Path> for IgnorePath {
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
         let can_path =
             into_absolute(path).map_err(|err| Error::FailedToCanonicalize(path.into(), err))?;
-        #[cfg_attr(not(feature = "async"), allow(clippy::useless_conversion))]
+        #[cfg_attr(
+            not(any(feature = "async-std", feature = "serde")),
+            allow(clippy::useless_conversion)
+        )]
         if can_path.is_file() {
             Ok(Self::Whole(can_path.into()))
         } else if can_path.is_dir() {
@@ -152,24 +756,687 @@ pub fn into_absolute_async<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf>
     // TODO FIXME NOTE We use `std::fs::canonicalize` here, even though there is `async_std::fs::canonicalize`, because we can not use async in this trait, and using a special async version of this trait would be an anti-pattern:
     // TODO FIXME NOTE <https://users.rust-lang.org/t/is-there-a-way-to-await-inside-a-from-or-tryfrom/68576/5>
     // TODO FIXME NOTE BUT: The anti-pattern is actually, to use such an expensive function in a TryFrom at all!
-    #[cfg_attr(not(feature = "async"), allow(clippy::useless_conversion))]
+    #[cfg_attr(
+        not(any(feature = "async-std", feature = "serde")),
+        allow(clippy::useless_conversion)
+    )]
     into_absolute(path.as_ref()).map(PathBuf::from)
 }
 
+/// Like [`into_absolute`], but also works for paths that do not exist (anymore),
+/// as long as one of their ancestors does,
+/// e.g. for files reported as removed by [`crate::file_traversal`].
+#[cfg(feature = "file_traversal_sync")]
+fn into_absolute_lenient(path: &Path) -> std::io::Result<std::path::PathBuf> {
+    match into_absolute(path) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                return Err(err);
+            };
+            let existing_parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            Ok(into_absolute_lenient(existing_parent)?.join(name))
+        }
+        abs_path_res => abs_path_res,
+    }
+}
+
 /// Parses the argument into an [`IgnorePath`].
 ///
+/// The syntax of the pattern may be given explicitly with a prefix:
+///
+/// - `"path:"` - an existing file or directory
+/// - `"prefix:"` - a path prefix, which does not need to exist
+/// - `"glob:"` - a glob, supporting `*` (matching any number of any chars,
+///   including `'/'`) and `?` (matching any single char)
+/// - `"re:"` - a regular expression
+/// - `"gitignore:"` - a single pattern as used in `.gitignore` files
+///
+/// Without a prefix, the pattern is considered a glob
+/// if it contains any of `'*'` or `'?'`, and a path otherwise
+/// (see [`PatternSyntax::detect`]).
+///
+/// Relative paths, prefixes and globs (not starting with a wildcard)
+/// are resolved against the current working directory,
+/// because [`IgnorePath::matches`] is applied to absolute paths.
+///
 /// # Errors
 ///
-/// If the argument is not a valid path glob.
-pub fn parse(path_str: &str) -> Result<IgnorePath, String> {
-    IgnorePath::try_from(path_str).map_err(|err| format!("{err:?}"))
+/// If the argument is not a valid pattern in the given or detected syntax.
+pub fn parse(pattern_str: &str) -> Result<IgnorePath, Error> {
+    let (syntax, pattern) = PatternSyntax::split(pattern_str);
+    parse_as(
+        syntax.unwrap_or_else(|| PatternSyntax::detect(pattern)),
+        pattern,
+    )
 }
 
-/// Checks if the argument is a valid ignore path (=> path glob).
+/// Parses the argument into an [`IgnorePath`],
+/// interpreting it in the given syntax.
+/// `pattern` is expected to *not* contain the syntax prefix.
+///
+/// See [`parse`] for details.
 ///
 /// # Errors
-/// If the argument is not a valid path glob.
-// pub fn is_valid(path_str: &str) -> Result<(), String> {
-pub fn is_valid<S: AsRef<str>>(path_str: S) -> Result<(), String> {
-    parse(path_str.as_ref()).map(|_| ())
+///
+/// If the argument is not a valid pattern in the given syntax.
+pub fn parse_as(syntax: PatternSyntax, pattern: &str) -> Result<IgnorePath, Error> {
+    if pattern.is_empty() {
+        return Err(Error::EmptyPattern(syntax));
+    }
+    match syntax {
+        PatternSyntax::Path => IgnorePath::try_from(pattern),
+        PatternSyntax::Prefix => {
+            let path = Path::new(pattern);
+            let abs_path = into_absolute(path)
+                .or_else(|_| std::path::absolute(path))
+                .map_err(|err| Error::FailedToCanonicalize(path.into(), err))?;
+            #[cfg_attr(
+                not(any(feature = "async-std", feature = "serde")),
+                allow(clippy::useless_conversion)
+            )]
+            Ok(IgnorePath::Prefix(abs_path.into()))
+        }
+        PatternSyntax::Glob => {
+            if pattern.starts_with(['*', '?']) {
+                Ok(IgnorePath::Glob(WildMatch::new(pattern)))
+            } else {
+                // Normalized like the paths it gets matched against,
+                // e.g. "./build/*" -> "/cwd/build/*"
+                let abs_pattern = if Path::new(pattern).is_absolute() {
+                    crate::path_utils::normalize(pattern)
+                } else {
+                    crate::path_utils::normalize(current_dir_abs()?.join(pattern))
+                };
+                Ok(IgnorePath::Glob(WildMatch::new(&to_match_str(abs_pattern))))
+            }
+        }
+        PatternSyntax::Regex => Regex::new(pattern)
+            .map(IgnorePath::Regex)
+            .map_err(|err| Error::InvalidRegex(pattern.to_owned(), err)),
+        PatternSyntax::Gitignore => {
+            GitignorePattern::new_in_cwd(pattern).map(IgnorePath::Gitignore)
+        }
+    }
+}
+
+/// Checks if the argument is a valid ignore pattern.
+/// See [`parse`] for the supported syntax.
+///
+/// # Errors
+///
+/// If the argument is not a valid ignore pattern.
+pub fn is_valid<S: AsRef<str>>(pattern_str: S) -> Result<(), Error> {
+    parse(pattern_str.as_ref()).map(|_| ())
+}
+
+/// A single pattern as used in `.gitignore` files,
+/// applied to absolute paths.
+///
+/// Supported are:
+///
+/// - `*` matching anything except `'/'`
+/// - `?` matching any single char except `'/'`
+/// - `[...]` and `[!...]` character classes
+/// - leading `**/`, trailing `/**` and inner `/**/`
+/// - a trailing `'/'`, restricting the match to directories
+///   (which - in this case - means: to paths *within* the directory)
+/// - `\` escaping the next char
+///
+/// Patterns containing a `'/'` (other then a trailing one)
+/// are anchored to a base directory,
+/// all others may match at any depth.
+///
+/// Negated patterns (starting with `'!'`) match
+/// the same paths as their non-negated counterparts,
+/// but re-include them (see [`IgnoreRules`]).
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "GitignorePatternSpec", into = "GitignorePatternSpec")
+)]
+pub struct GitignorePattern {
+    pattern: String,
+    base: Option<std::path::PathBuf>,
+    negated: bool,
+    regex: Regex,
+}
+
+/// The serialized form of a [`GitignorePattern`].
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct GitignorePatternSpec {
+    pattern: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base: Option<std::path::PathBuf>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GitignorePatternSpec> for GitignorePattern {
+    type Error = Error;
+
+    fn try_from(spec: GitignorePatternSpec) -> Result<Self, Self::Error> {
+        Self::new(&spec.pattern, spec.base.as_deref())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<GitignorePattern> for GitignorePatternSpec {
+    fn from(pattern: GitignorePattern) -> Self {
+        Self {
+            pattern: pattern.pattern,
+            base: pattern.base,
+        }
+    }
+}
+
+impl GitignorePattern {
+    /// Creates a new gitignore pattern.
+    ///
+    /// Anchored patterns (see [`Self`]) are anchored to `base`,
+    /// which should be an absolute path.
+    /// If `base` is `None`, they are anchored to the file-system root.
+    ///
+    /// # Errors
+    ///
+    /// If `pattern` is not a valid (and supported) gitignore pattern.
+    pub fn new(pattern: &str, base: Option<&std::path::Path>) -> Result<Self, Error> {
+        let (positive_pattern, negated) = pattern
+            .strip_prefix('!')
+            .map_or((pattern, false), |stripped| (stripped, true));
+        let regex_str = gitignore_to_regex(positive_pattern, base)
+            .map_err(|reason| Error::InvalidGitignorePattern(pattern.to_owned(), reason))?;
+        let regex =
+            Regex::new(&regex_str).map_err(|err| Error::InvalidRegex(pattern.to_owned(), err))?;
+        Ok(Self {
+            pattern: pattern.to_owned(),
+            base: base.map(std::path::Path::to_path_buf),
+            negated,
+            regex,
+        })
+    }
+
+    /// Creates a new gitignore pattern,
+    /// anchored to the current working directory.
+    ///
+    /// # Errors
+    ///
+    /// If `pattern` is not a valid (and supported) gitignore pattern,
+    /// or the current working directory could not be determined.
+    pub fn new_in_cwd(pattern: &str) -> Result<Self, Error> {
+        Self::new(pattern, Some(&current_dir_abs()?))
+    }
+
+    /// The pattern as originally given.
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The directory anchored patterns are relative to.
+    #[must_use]
+    pub fn base(&self) -> Option<&std::path::Path> {
+        self.base.as_deref()
+    }
+
+    /// Whether this pattern started with `'!'`.
+    #[must_use]
+    pub const fn is_negation(&self) -> bool {
+        self.negated
+    }
+
+    #[must_use]
+    pub fn matches(&self, abs_path: &Path) -> bool {
+        self.regex.is_match(&to_match_str(abs_path))
+    }
+}
+
+impl Display for GitignorePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.pattern.fmt(f)
+    }
+}
+
+/// Converts a path to the string representation patterns are matched against,
+/// which uses `'/'` as separator on all platforms.
+fn to_match_str<P: AsRef<std::path::Path>>(path: P) -> String {
+    let path_str = path.as_ref().to_string_lossy();
+    if std::path::MAIN_SEPARATOR == '/' {
+        path_str.into_owned()
+    } else {
+        path_str.replace(std::path::MAIN_SEPARATOR, "/")
+    }
+}
+
+fn current_dir_abs() -> Result<std::path::PathBuf, Error> {
+    std::env::current_dir()
+        .and_then(into_absolute)
+        .map_err(|err| Error::FailedToCanonicalize(PathBuf::from("."), err))
+}
+
+/// Translates a single, non-negated gitignore pattern
+/// into an (anchored) regex string,
+/// to be applied to absolute paths as returned by [`to_match_str`].
+fn gitignore_to_regex(
+    pattern: &str,
+    base: Option<&std::path::Path>,
+) -> Result<String, GitignorePatternError> {
+    let (unanchored_body, dir_only) = pattern
+        .strip_suffix('/')
+        .map_or((pattern, false), |stripped| (stripped, true));
+    let anchored = unanchored_body.contains('/');
+    let body = unanchored_body.strip_prefix('/').unwrap_or(unanchored_body);
+
+    let mut regex = String::from("^");
+    if let Some(base_dir) = base {
+        // Only components below the base may match,
+        // also for unanchored patterns
+        regex.push_str(&regex::escape(to_match_str(base_dir).trim_end_matches('/')));
+        regex.push('/');
+        if !anchored {
+            regex.push_str("(?:.*/)?");
+        }
+    } else if anchored {
+        regex.push('/');
+    } else {
+        regex.push_str("(?:.*/)?");
+    }
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut idx = 0;
+    while let Some(&chr) = chars.get(idx) {
+        match chr {
+            '*' if chars.get(idx + 1) == Some(&'*') => {
+                let at_start = idx == 0 || chars.get(idx - 1) == Some(&'/');
+                let after = chars.get(idx + 2).copied();
+                match (at_start, after) {
+                    (true, Some('/')) => {
+                        // "**/" -> zero or more directories
+                        regex.push_str("(?:.*/)?");
+                        idx += 3;
+                    }
+                    (true, None) => {
+                        // trailing "/**" -> everything inside
+                        regex.push_str(".*");
+                        idx += 2;
+                    }
+                    _ => {
+                        // other consecutive asterisks are regular asterisks
+                        regex.push_str("[^/]*");
+                        while chars.get(idx) == Some(&'*') {
+                            idx += 1;
+                        }
+                    }
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                idx = push_char_class(&chars, idx, &mut regex)?;
+                continue;
+            }
+            '\\' => {
+                let escaped = chars
+                    .get(idx + 1)
+                    .ok_or(GitignorePatternError::TrailingBackslash)?;
+                regex.push_str(&regex::escape(&escaped.to_string()));
+                idx += 1;
+            }
+            other => regex.push_str(&regex::escape(&other.to_string())),
+        }
+        idx += 1;
+    }
+
+    regex.push_str(if dir_only { "/" } else { "(?:/|$)" });
+    Ok(regex)
+}
+
+/// Translates the gitignore character class starting at `chars[start]` (`'['`)
+/// into a regex character class.
+///
+/// Returns the index right after the closing `']'`.
+fn push_char_class(
+    chars: &[char],
+    start: usize,
+    regex: &mut String,
+) -> Result<usize, GitignorePatternError> {
+    let mut idx = start + 1;
+    regex.push('[');
+    if matches!(chars.get(idx), Some('!' | '^')) {
+        regex.push('^');
+        idx += 1;
+    }
+    let content_start = idx;
+    loop {
+        match chars.get(idx) {
+            None => return Err(GitignorePatternError::UnclosedCharClass),
+            // a ']' right at the start is a literal
+            Some(']') if idx != content_start => break,
+            Some('\\') => {
+                let escaped = *chars
+                    .get(idx + 1)
+                    .ok_or(GitignorePatternError::UnclosedCharClass)?;
+                if escaped.is_ascii_punctuation() {
+                    regex.push('\\');
+                }
+                regex.push(escaped);
+                idx += 1;
+            }
+            Some(&chr @ (']' | '[' | '&' | '~' | '^')) => {
+                regex.push('\\');
+                regex.push(chr);
+            }
+            Some(&chr) => regex.push(chr),
+        }
+        idx += 1;
+    }
+    regex.push(']');
+    Ok(idx + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gitignore_matches_check(pattern: &str, path: &str, expected: bool) {
+        let gitignore_pattern = GitignorePattern::new(pattern, Some(std::path::Path::new("/base")))
+            .expect("Test pattern should be valid");
+        assert_eq!(
+            gitignore_pattern.matches(Path::new(path)),
+            expected,
+            "pattern: '{pattern}', path: '{path}'"
+        );
+    }
+
+    macro_rules! gitignore_matches {
+        ($($name:ident: { pattern: $pattern:expr, path: $path:expr, expected: $expected:expr, },)*) => {
+        $(
+            #[test]
+            fn $name() {
+                gitignore_matches_check($pattern, $path, $expected);
+            }
+        )*
+        }
+    }
+
+    gitignore_matches! {
+        gitignore_name_anywhere: {
+            pattern: "target",
+            path: "/base/sub/target/debug/x.o",
+            expected: true,
+        },
+        gitignore_name_file: {
+            pattern: "target",
+            path: "/base/sub/target",
+            expected: true,
+        },
+        gitignore_name_partial: {
+            pattern: "target",
+            path: "/base/sub/targets/x.o",
+            expected: false,
+        },
+        gitignore_star_ext: {
+            pattern: "*.o",
+            path: "/base/sub/x.o",
+            expected: true,
+        },
+        gitignore_star_no_slash: {
+            pattern: "sub/*.o",
+            path: "/base/sub/deeper/x.o",
+            expected: false,
+        },
+        gitignore_anchored: {
+            pattern: "/build",
+            path: "/base/build/x",
+            expected: true,
+        },
+        gitignore_anchored_not_deeper: {
+            pattern: "/build",
+            path: "/base/sub/build/x",
+            expected: false,
+        },
+        gitignore_anchored_middle_slash: {
+            pattern: "doc/gen",
+            path: "/other/doc/gen/x",
+            expected: false,
+        },
+        gitignore_dir_only_content: {
+            pattern: "build/",
+            path: "/base/sub/build/x",
+            expected: true,
+        },
+        gitignore_dir_only_file: {
+            pattern: "build/",
+            path: "/base/sub/build",
+            expected: false,
+        },
+        gitignore_leading_double_star: {
+            pattern: "**/gen/*.rs",
+            path: "/base/a/b/gen/x.rs",
+            expected: true,
+        },
+        gitignore_leading_double_star_zero_dirs: {
+            pattern: "**/gen/*.rs",
+            path: "/base/gen/x.rs",
+            expected: true,
+        },
+        gitignore_trailing_double_star: {
+            pattern: "logs/**",
+            path: "/base/logs/a/b.log",
+            expected: true,
+        },
+        gitignore_inner_double_star: {
+            pattern: "a/**/b",
+            path: "/base/a/x/y/b",
+            expected: true,
+        },
+        gitignore_question_mark: {
+            pattern: "?.txt",
+            path: "/base/a.txt",
+            expected: true,
+        },
+        gitignore_char_class: {
+            pattern: "[ab].txt",
+            path: "/base/b.txt",
+            expected: true,
+        },
+        gitignore_char_class_negated: {
+            pattern: "[!ab].txt",
+            path: "/base/b.txt",
+            expected: false,
+        },
+        gitignore_escaped: {
+            pattern: "\\*.txt",
+            path: "/base/x.txt",
+            expected: false,
+        },
+        gitignore_regex_meta_literal: {
+            pattern: "a+b.txt",
+            path: "/base/a+b.txt",
+            expected: true,
+        },
+    }
+
+    #[test]
+    fn gitignore_below_base_only() {
+        let base = std::path::Path::new("/home/u/tmp/repo");
+        let gitignore_pattern =
+            GitignorePattern::new("tmp", Some(base)).expect("Test pattern should be valid");
+        assert!(!gitignore_pattern.matches(Path::new("/home/u/tmp/repo/src/main.rs")));
+        assert!(gitignore_pattern.matches(Path::new("/home/u/tmp/repo/src/tmp/x")));
+        assert!(gitignore_pattern.matches(Path::new("/home/u/tmp/repo/tmp")));
+        assert!(!gitignore_pattern.matches(Path::new("/home/u/tmp/other/x")));
+    }
+
+    #[test]
+    fn gitignore_invalid() {
+        assert!(matches!(
+            GitignorePattern::new("[ab", None),
+            Err(Error::InvalidGitignorePattern(
+                _,
+                GitignorePatternError::UnclosedCharClass
+            ))
+        ));
+        assert!(matches!(
+            GitignorePattern::new("ab\\", None),
+            Err(Error::InvalidGitignorePattern(
+                _,
+                GitignorePatternError::TrailingBackslash
+            ))
+        ));
+    }
+
+    fn rules_from(patterns: &[&str]) -> IgnoreRules {
+        patterns
+            .iter()
+            .map(|pattern| {
+                GitignorePattern::new(pattern, Some(std::path::Path::new("/base")))
+                    .map(IgnorePath::Gitignore)
+                    .map(IgnoreRule::from)
+                    .expect("Test pattern should be valid")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rules_last_match_wins() {
+        let rules = rules_from(&["*.log", "!keep.log", "/keep.log"]);
+        let decision = rules.matching(Path::new("/base/sub/keep.log"));
+        assert_eq!(decision.map(|rule_match| rule_match.index), Some(1));
+        assert!(!rules.is_ignored(Path::new("/base/sub/keep.log")));
+        assert!(rules.is_ignored(Path::new("/base/keep.log")));
+        assert!(rules.is_ignored(Path::new("/base/sub/other.log")));
+        assert!(rules.matching(Path::new("/base/sub/main.rs")).is_none());
+    }
+
+    #[test]
+    fn rules_case_insensitive() -> Result<(), Error> {
+        let mut rules = rules_from(&["*.LOG", "/Build/"]);
+        rules.push(IgnorePath::Regex(
+            Regex::new(r"\.(Tmp|Bak)$").expect("Valid regex"),
+        ))?;
+        rules.push(IgnorePath::Glob(WildMatch::new("*/CACHE/*")))?;
+        assert!(!rules.is_ignored(Path::new("/base/x.log")));
+        rules.set_options(MatchOptions::default().with_case_insensitive(true))?;
+        assert!(rules.is_ignored(Path::new("/base/x.log")));
+        assert!(rules.is_ignored(Path::new("/BASE/build/x")));
+        assert!(rules.is_ignored(Path::new("/base/x.TMP")));
+        assert!(rules.is_ignored(Path::new("/base/cache/x")));
+        assert!(!rules.is_ignored(Path::new("/base/x.txt")));
+        Ok(())
+    }
+
+    #[cfg(feature = "unicode_normalization")]
+    #[test]
+    fn rules_normalized() -> Result<(), Error> {
+        // "é" composed (NFC) in the pattern, decomposed (NFD) in the path
+        let mut rules = rules_from(&["caf\u{e9}.txt"]);
+        let nfd_path = Path::new("/base/cafe\u{301}.txt");
+        assert!(!rules.is_ignored(nfd_path));
+        rules.set_options(MatchOptions::default().with_normalization(Some(Normalization::Nfc)))?;
+        assert!(rules.is_ignored(nfd_path));
+        Ok(())
+    }
+
+    #[test]
+    fn rules_matching_dir() {
+        let rules = rules_from(&["build/", "*.log"]);
+        assert!(rules.matching(Path::new("/base/build")).is_none());
+        assert!(rules.matching_dir(Path::new("/base/build")).is_some());
+        assert!(rules.matching_dir(Path::new("/base/src")).is_none());
+    }
+
+    #[test]
+    fn rule_match_display() {
+        let rules: IgnoreRules = rules_from(&["*.log"])
+            .rules()
+            .iter()
+            .cloned()
+            .map(|rule| IgnoreRule {
+                source: Some(RuleSource {
+                    file: PathBuf::from(".gitignore"),
+                    line: 3,
+                }),
+                ..rule
+            })
+            .collect();
+        let rule_match = rules
+            .matching(Path::new("/base/x.log"))
+            .expect("Should match");
+        assert_eq!(
+            rule_match.to_string(),
+            "rule #0 '*.log' (from .gitignore:3)"
+        );
+    }
+
+    #[test]
+    fn gitignore_lines() {
+        assert_eq!(gitignore_line_pattern("# comment"), None);
+        assert_eq!(gitignore_line_pattern("   "), None);
+        assert_eq!(gitignore_line_pattern("*.o  "), Some("*.o"));
+        assert_eq!(gitignore_line_pattern("x\\  "), Some("x\\ "));
+        assert_eq!(gitignore_line_pattern("\\#x"), Some("\\#x"));
+    }
+
+    #[test]
+    fn pattern_syntax_split() {
+        assert_eq!(
+            PatternSyntax::split("glob:*.rs"),
+            (Some(PatternSyntax::Glob), "*.rs")
+        );
+        assert_eq!(
+            PatternSyntax::split("re:^a$"),
+            (Some(PatternSyntax::Regex), "^a$")
+        );
+        assert_eq!(PatternSyntax::split("C:\\x"), (None, "C:\\x"));
+        assert_eq!(PatternSyntax::detect("src/*.rs"), PatternSyntax::Glob);
+        assert_eq!(PatternSyntax::detect("src/main.rs"), PatternSyntax::Path);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            parse("glob:"),
+            Err(Error::EmptyPattern(PatternSyntax::Glob))
+        ));
+        assert!(matches!(
+            parse("re:(unclosed"),
+            Err(Error::InvalidRegex(..))
+        ));
+        assert!(matches!(
+            parse("path:/this/path/does/not/exist"),
+            Err(Error::FailedToCanonicalize(..))
+        ));
+    }
+
+    #[test]
+    fn parse_glob_normalized() -> Result<(), Error> {
+        let cwd = current_dir_abs()?;
+        let ignore_path = parse("glob:./build/../out/*.o")?;
+        assert!(ignore_path.matches(Path::new(&cwd.join("out/x.o"))));
+        assert!(!ignore_path.matches(Path::new(&cwd.join("build/x.o"))));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_regex() {
+        let ignore_path = parse(r"re:\.o$").expect("Test regex should be valid");
+        assert!(ignore_path.matches(Path::new("/a/b.o")));
+        assert!(!ignore_path.matches(Path::new("/a/b.rs")));
+    }
+
+    #[cfg(feature = "file_traversal_sync")]
+    #[test]
+    fn absolute_lenient_missing() -> std::io::Result<()> {
+        assert_eq!(
+            into_absolute_lenient(Path::new("no/such/file.txt"))?,
+            into_absolute(Path::new("."))?.join("no/such/file.txt")
+        );
+        Ok(())
+    }
 }
@@ -2,17 +2,26 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-#[cfg(feature = "file_traversal")]
+#[cfg(feature = "async")]
+mod async_runtime;
+#[cfg(feature = "file_traversal_sync")]
 pub mod file_traversal;
+#[cfg(feature = "glob")]
+pub mod glob;
 #[cfg(feature = "ignore_path")]
 pub mod ignore_path;
 #[cfg(feature = "logging")]
 pub mod logging;
-#[cfg(all(feature = "async", feature = "serde"))]
+#[cfg(feature = "serde")]
 pub mod path_buf;
+#[cfg(feature = "path_expand")]
+pub mod path_expand;
+pub mod path_utils;
 #[cfg(feature = "std_error")]
 pub mod std_error;
 mod std_streams;
+#[cfg(feature = "validated_path")]
+pub mod validated_path;
 
 pub use std_streams::*;
 
@@ -3,24 +3,40 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
-use async_std::path::Iter;
-use async_std::path::Path as AsyncPath;
-use async_std::path::PathBuf as AsyncPathBuf;
+#[cfg(feature = "async-std")]
+use async_std::path::{Iter, Path as InnerPath, PathBuf as InnerPathBuf};
 use std::path::Path as StdPath;
 use std::path::PathBuf as StdPathBuf;
-
-use std::{ffi::OsStr, fmt::Display, path::StripPrefixError, str::FromStr};
+#[cfg(not(feature = "async-std"))]
+use std::path::{Iter, Path as InnerPath, PathBuf as InnerPathBuf};
+
+use std::{
+    borrow::{Borrow, Cow},
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    ops::Deref,
+    path::StripPrefixError,
+    str::FromStr,
+};
 use {
     serde::{
-        de::{Deserialize, Deserializer, Unexpected, Visitor},
-        ser::{Serialize, Serializer},
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{MapAccess, Unexpected, Visitor, value::MapAccessDeserializer},
     },
     std::{fmt, str},
 };
 
 /// This wrapper exists so we can implement serde on it.
+///
+/// It wraps an [`async_std::path::PathBuf`] if the `async-std` feature is enabled,
+/// and a [`std::path::PathBuf`] otherwise.
+/// It dereferences to the respective `Path`,
+/// so all of its (borrowing) methods are available,
+/// and offers the owning methods of the wrapped type.
+/// The methods that query the file system (e.g. [`Self::is_file`])
+/// are synchronous in either case.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct PathBuf(AsyncPathBuf);
+pub struct PathBuf(InnerPathBuf);
 
 impl Default for PathBuf {
     fn default() -> Self {
@@ -30,8 +46,9 @@ impl Default for PathBuf {
 
 impl PathBuf {
     #[must_use]
+    #[cfg_attr(not(feature = "async-std"), allow(clippy::missing_const_for_fn))]
     pub fn new() -> Self {
-        Self(AsyncPathBuf::new())
+        Self(InnerPathBuf::new())
     }
 
     #[must_use]
@@ -54,15 +71,45 @@ impl PathBuf {
         self.0.is_absolute()
     }
 
-    pub async fn is_file(&self) -> bool {
+    /// Whether this points at a regular file (following symlinks).
+    ///
+    /// This is synchronous in all configurations,
+    /// so enabling the `async-std` feature (e.g. by an other crate)
+    /// does not change it; see also `is_file_async`.
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        StdPath::new(self.as_os_str()).is_file()
+    }
+
+    /// Whether this points at a directory (following symlinks),
+    /// see [`Self::is_file`].
+    #[must_use]
+    pub fn is_dir(&self) -> bool {
+        StdPath::new(self.as_os_str()).is_dir()
+    }
+
+    /// Whether this points at an existing entity (following symlinks),
+    /// see [`Self::is_file`].
+    #[must_use]
+    pub fn exists(&self) -> bool {
+        StdPath::new(self.as_os_str()).exists()
+    }
+
+    /// Like [`Self::is_file`], but without blocking the current task.
+    #[cfg(feature = "async-std")]
+    pub async fn is_file_async(&self) -> bool {
         self.0.is_file().await
     }
 
-    pub async fn is_dir(&self) -> bool {
+    /// Like [`Self::is_dir`], but without blocking the current task.
+    #[cfg(feature = "async-std")]
+    pub async fn is_dir_async(&self) -> bool {
         self.0.is_dir().await
     }
 
-    pub async fn exists(&self) -> bool {
+    /// Like [`Self::exists`], but without blocking the current task.
+    #[cfg(feature = "async-std")]
+    pub async fn exists_async(&self) -> bool {
         self.0.exists().await
     }
 
@@ -77,17 +124,17 @@ impl PathBuf {
     }
 
     #[must_use]
-    pub fn parent(&self) -> Option<&AsyncPath> {
+    pub fn parent(&self) -> Option<&InnerPath> {
         self.0.parent()
     }
 
     #[must_use]
-    pub fn as_path(&self) -> &AsyncPath {
+    pub fn as_path(&self) -> &InnerPath {
         self.0.as_path()
     }
 
     #[must_use]
-    pub fn join<P: AsRef<AsyncPath>>(&self, path: P) -> Self {
+    pub fn join<P: AsRef<InnerPath>>(&self, path: P) -> Self {
         Self(self.0.join(path))
     }
 
@@ -104,7 +151,7 @@ impl PathBuf {
     /// # Examples
     ///
     /// ```
-    /// use async_std::path::{Path, PathBuf};
+    /// use std::path::{Path, PathBuf};
     ///
     /// let path = Path::new("/test/haha/foo.txt");
     ///
@@ -119,17 +166,95 @@ impl PathBuf {
     /// let prefix = PathBuf::from("/test/");
     /// assert_eq!(path.strip_prefix(prefix), Ok(Path::new("haha/foo.txt")));
     /// ```
-    pub fn strip_prefix<P>(&self, base: P) -> Result<&AsyncPath, StripPrefixError>
+    pub fn strip_prefix<P>(&self, base: P) -> Result<&InnerPath, StripPrefixError>
     where
-        P: AsRef<AsyncPath>,
+        P: AsRef<InnerPath>,
     {
         self.0.strip_prefix(base)
     }
 
-    #[must_use]
+    #[cfg_attr(feature = "async-std", must_use)]
     pub fn iter(&self) -> Iter<'_> {
         self.0.iter()
     }
+
+    /// Extends `self` with `path`,
+    /// replacing it if `path` is absolute.
+    /// See [`std::path::PathBuf::push`].
+    pub fn push<P: AsRef<InnerPath>>(&mut self, path: P) {
+        self.0.push(path);
+    }
+
+    /// Truncates `self` to its parent.
+    ///
+    /// Returns `false` and does nothing if there is no parent,
+    /// see [`std::path::PathBuf::pop`].
+    pub fn pop(&mut self) -> bool {
+        self.0.pop()
+    }
+
+    /// Replaces the last component of `self` with `file_name`,
+    /// see [`std::path::PathBuf::set_file_name`].
+    pub fn set_file_name<S: AsRef<OsStr>>(&mut self, file_name: S) {
+        self.0.set_file_name(file_name);
+    }
+
+    /// Replaces the extension of the file name of `self`,
+    /// or removes it if `extension` is empty.
+    ///
+    /// Returns `false` and does nothing if there is no file name,
+    /// see [`std::path::PathBuf::set_extension`].
+    pub fn set_extension<S: AsRef<OsStr>>(&mut self, extension: S) -> bool {
+        self.0.set_extension(extension)
+    }
+
+    /// Like `Path::with_file_name`, but returns this type.
+    #[must_use]
+    pub fn with_file_name<S: AsRef<OsStr>>(&self, file_name: S) -> Self {
+        Self(self.0.with_file_name(file_name))
+    }
+
+    /// Like `Path::with_extension`, but returns this type.
+    #[must_use]
+    pub fn with_extension<S: AsRef<OsStr>>(&self, extension: S) -> Self {
+        Self(self.0.with_extension(extension))
+    }
+
+    #[must_use]
+    pub fn into_os_string(self) -> OsString {
+        self.0.into_os_string()
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> InnerPathBuf {
+        self.0
+    }
+}
+
+impl Deref for PathBuf {
+    type Target = InnerPath;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_path()
+    }
+}
+
+impl Borrow<InnerPath> for PathBuf {
+    fn borrow(&self) -> &InnerPath {
+        self.0.as_path()
+    }
+}
+
+impl<P: AsRef<InnerPath>> Extend<P> for PathBuf {
+    fn extend<I: IntoIterator<Item = P>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl<P: AsRef<InnerPath>> FromIterator<P> for PathBuf {
+    fn from_iter<I: IntoIterator<Item = P>>(iter: I) -> Self {
+        Self(InnerPathBuf::from_iter(iter))
+    }
 }
 
 impl Display for PathBuf {
@@ -140,7 +265,7 @@ impl Display for PathBuf {
 
 impl<'a> IntoIterator for &'a PathBuf {
     type Item = &'a std::ffi::OsStr;
-    type IntoIter = async_std::path::Iter<'a>;
+    type IntoIter = Iter<'a>;
     fn into_iter(self) -> Self::IntoIter {
         self.iter()
     }
@@ -152,8 +277,9 @@ impl AsRef<StdPath> for PathBuf {
     }
 }
 
-impl AsRef<AsyncPath> for PathBuf {
-    fn as_ref(&self) -> &AsyncPath {
+#[cfg(feature = "async-std")]
+impl AsRef<InnerPath> for PathBuf {
+    fn as_ref(&self) -> &InnerPath {
         self.0.as_ref()
     }
 }
@@ -162,55 +288,60 @@ impl FromStr for PathBuf {
     type Err = core::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        AsyncPathBuf::from_str(s).map(PathBuf)
+        InnerPathBuf::from_str(s).map(PathBuf)
     }
 }
 
-impl From<&AsyncPath> for PathBuf {
-    fn from(path: &AsyncPath) -> Self {
-        Self(AsyncPathBuf::from(path))
+#[cfg(feature = "async-std")]
+impl From<&InnerPath> for PathBuf {
+    fn from(path: &InnerPath) -> Self {
+        Self(InnerPathBuf::from(path))
     }
 }
 
 impl From<&StdPath> for PathBuf {
     fn from(path: &StdPath) -> Self {
-        Self(AsyncPathBuf::from(path))
+        Self(InnerPathBuf::from(path))
     }
 }
 
 impl From<&StdPathBuf> for PathBuf {
     fn from(path: &StdPathBuf) -> Self {
-        Self(AsyncPathBuf::from(path))
+        Self(InnerPathBuf::from(path))
     }
 }
 
 impl From<StdPathBuf> for PathBuf {
+    #[cfg_attr(not(feature = "async-std"), allow(clippy::useless_conversion))]
     fn from(path: StdPathBuf) -> Self {
-        Self(AsyncPathBuf::from(path))
+        Self(InnerPathBuf::from(path))
     }
 }
 
-impl From<AsyncPathBuf> for PathBuf {
-    fn from(path: AsyncPathBuf) -> Self {
+#[cfg(feature = "async-std")]
+impl From<InnerPathBuf> for PathBuf {
+    fn from(path: InnerPathBuf) -> Self {
         Self(path)
     }
 }
 
 impl From<&str> for PathBuf {
     fn from(path: &str) -> Self {
-        Self(AsyncPathBuf::from(path))
+        Self(InnerPathBuf::from(path))
     }
 }
 
-impl From<&PathBuf> for AsyncPathBuf {
+#[cfg(feature = "async-std")]
+impl From<&PathBuf> for InnerPathBuf {
     fn from(path: &PathBuf) -> Self {
-        Self::from(<PathBuf as AsRef<AsyncPath>>::as_ref(path))
+        Self::from(<PathBuf as AsRef<InnerPath>>::as_ref(path))
     }
 }
 
-impl From<PathBuf> for AsyncPathBuf {
+#[cfg(feature = "async-std")]
+impl From<PathBuf> for InnerPathBuf {
     fn from(path: PathBuf) -> Self {
-        Self::from(<PathBuf as AsRef<AsyncPath>>::as_ref(&path))
+        Self::from(<PathBuf as AsRef<InnerPath>>::as_ref(&path))
     }
 }
 
@@ -226,9 +357,120 @@ impl From<PathBuf> for StdPathBuf {
     }
 }
 
-struct PathBufVisitor;
+/// How a path is stored if it can not be written as a plain string,
+/// either because it is not valid UTF-8,
+/// or because the format is not human-readable.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Encoded<'a> {
+    Utf8(Cow<'a, str>),
+    /// The raw bytes of a Unix path
+    Bytes(Cow<'a, [u8]>),
+    /// The UTF-16 code units of a Windows path
+    Wide(Cow<'a, [u16]>),
+}
+
+impl<'a> Encoded<'a> {
+    fn new(path: &'a OsStr) -> Self {
+        path.to_str()
+            .map_or_else(|| Self::raw(path), |utf8| Self::Utf8(Cow::Borrowed(utf8)))
+    }
+
+    #[cfg(unix)]
+    fn raw(path: &'a OsStr) -> Self {
+        use std::os::unix::ffi::OsStrExt;
+        Self::Bytes(Cow::Borrowed(path.as_bytes()))
+    }
+
+    #[cfg(windows)]
+    fn raw(path: &'a OsStr) -> Self {
+        use std::os::windows::ffi::OsStrExt;
+        Self::Wide(Cow::Owned(path.encode_wide().collect()))
+    }
+
+    // NOTE We know of no other way to get at the raw path on these platforms
+    #[cfg(not(any(unix, windows)))]
+    fn raw(path: &'a OsStr) -> Self {
+        Self::Utf8(path.to_string_lossy())
+    }
+
+    /// Decodes the path.
+    ///
+    /// This fails only for raw paths written on an other platform
+    /// that are not valid Unicode.
+    fn into_path(self) -> Result<PathBuf, String> {
+        let os_string = match self {
+            Self::Utf8(utf8) => OsString::from(utf8.into_owned()),
+            #[cfg(unix)]
+            Self::Bytes(bytes) => {
+                use std::os::unix::ffi::OsStringExt;
+                OsString::from_vec(bytes.into_owned())
+            }
+            #[cfg(not(unix))]
+            Self::Bytes(bytes) => String::from_utf8(bytes.into_owned())
+                .map_err(|err| format!("Unix path is not valid UTF-8: {err}"))?
+                .into(),
+            #[cfg(windows)]
+            Self::Wide(wide) => {
+                use std::os::windows::ffi::OsStringExt;
+                OsString::from_wide(&wide)
+            }
+            #[cfg(not(windows))]
+            Self::Wide(wide) => String::from_utf16(&wide)
+                .map_err(|err| format!("Windows path is not valid UTF-16: {err}"))?
+                .into(),
+        };
+        Ok(PathBuf(InnerPathBuf::from(os_string)))
+    }
+}
+
+/// Reads a path that is either a plain string,
+/// or an [`Encoded`] one.
+struct EncodedVisitor;
+
+impl<'de> Visitor<'de> for EncodedVisitor {
+    type Value = Encoded<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("path string or encoded path")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Encoded::Utf8(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Encoded::Utf8(Cow::Owned(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Encoded::Bytes(Cow::Owned(v.to_vec())))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        Encoded::deserialize(MapAccessDeserializer::new(map))
+    }
+}
+
+/// Reads a path from a string.
+struct PathBufVisitor {
+    /// Whether to replace invalid UTF-8 in bytes,
+    /// instead of failing
+    lossy: bool,
+}
 
-// #[cfg(feature = "serde")]
 impl Visitor<'_> for PathBufVisitor {
     type Value = PathBuf;
 
@@ -247,18 +489,143 @@ impl Visitor<'_> for PathBufVisitor {
     where
         E: serde::de::Error,
     {
+        if self.lossy {
+            return Ok(From::from(String::from_utf8_lossy(v).as_ref()));
+        }
         str::from_utf8(v)
             .map(From::from)
             .map_err(|_| serde::de::Error::invalid_value(Unexpected::Bytes(v), &self))
     }
 }
 
+/// (De-)Serializes paths without loss, also if they are not valid UTF-8.
+/// This is what the [`Serialize`] and [`Deserialize`] implementations
+/// of [`PathBuf`] use.
+///
+/// In human-readable formats, valid UTF-8 paths are written as plain strings,
+/// others as e.g. `{ "bytes": [...] }` (Unix) or `{ "wide": [...] }` (Windows).
+/// Other formats always use this tagged representation.
+///
+/// Use as `#[serde(with = "cli_utils_hoijui::path_buf::lossless")]`
+/// on fields of type [`PathBuf`], [`std::path::PathBuf`]
+/// or `async_std::path::PathBuf`.
+pub mod lossless {
+    use super::{Encoded, EncodedVisitor, PathBuf, StdPath};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+    /// # Errors
+    ///
+    /// If the serializer fails.
+    pub fn serialize<P, S>(path: &P, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        P: AsRef<StdPath>,
+        S: Serializer,
+    {
+        let os_str = path.as_ref().as_os_str();
+        match os_str.to_str() {
+            Some(utf8) if serializer.is_human_readable() => serializer.serialize_str(utf8),
+            _ => Encoded::new(os_str).serialize(serializer),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// If the input is no path,
+    /// or if it is a raw path from an other platform that is not valid Unicode.
+    pub fn deserialize<'de, D, P>(deserializer: D) -> Result<P, D::Error>
+    where
+        D: Deserializer<'de>,
+        P: From<PathBuf>,
+    {
+        let encoded = if deserializer.is_human_readable() {
+            deserializer.deserialize_any(EncodedVisitor)?
+        } else {
+            Encoded::deserialize(deserializer)?
+        };
+        encoded.into_path().map(P::from).map_err(D::Error::custom)
+    }
+}
+
+/// (De-)Serializes paths as plain strings,
+/// failing for paths that are not valid UTF-8.
+///
+/// Use as `#[serde(with = "cli_utils_hoijui::path_buf::string_only")]`,
+/// see [`lossless`].
+pub mod string_only {
+    use super::{PathBuf, PathBufVisitor, StdPath};
+    use serde::{Deserializer, Serializer, ser::Error as _};
+
+    /// # Errors
+    ///
+    /// If the path is not valid UTF-8,
+    /// or if the serializer fails.
+    pub fn serialize<P, S>(path: &P, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        P: AsRef<StdPath>,
+        S: Serializer,
+    {
+        let std_path = path.as_ref();
+        let utf8 = std_path.to_str().ok_or_else(|| {
+            S::Error::custom(format!("Path is not valid UTF-8: '{}'", std_path.display()))
+        })?;
+        serializer.serialize_str(utf8)
+    }
+
+    /// # Errors
+    ///
+    /// If the input is not a (UTF-8) string.
+    pub fn deserialize<'de, D, P>(deserializer: D) -> Result<P, D::Error>
+    where
+        D: Deserializer<'de>,
+        P: From<PathBuf>,
+    {
+        deserializer
+            .deserialize_string(PathBufVisitor { lossy: false })
+            .map(P::from)
+    }
+}
+
+/// (De-)Serializes paths as plain strings,
+/// replacing invalid UTF-8 with `U+FFFD`,
+/// so such paths do not survive a round-trip.
+///
+/// Use as `#[serde(with = "cli_utils_hoijui::path_buf::lossy")]`,
+/// see [`lossless`].
+pub mod lossy {
+    use super::{PathBuf, PathBufVisitor, StdPath};
+    use serde::{Deserializer, Serializer};
+
+    /// # Errors
+    ///
+    /// If the serializer fails.
+    pub fn serialize<P, S>(path: &P, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        P: AsRef<StdPath>,
+        S: Serializer,
+    {
+        serializer.serialize_str(&path.as_ref().to_string_lossy())
+    }
+
+    /// # Errors
+    ///
+    /// If the input is not a string.
+    pub fn deserialize<'de, D, P>(deserializer: D) -> Result<P, D::Error>
+    where
+        D: Deserializer<'de>,
+        P: From<PathBuf>,
+    {
+        deserializer
+            .deserialize_string(PathBufVisitor { lossy: true })
+            .map(P::from)
+    }
+}
+
 impl Serialize for PathBuf {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.as_path().as_os_str().serialize(serializer)
+        lossless::serialize(self, serializer)
     }
 }
 
@@ -267,6 +634,126 @@ impl<'de> Deserialize<'de> for PathBuf {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_string(PathBufVisitor)
+        lossless::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutators() {
+        let mut path = PathBuf::from("/base/dir");
+        path.push("file.tar.gz");
+        assert_eq!(path.file_stem(), Some(OsStr::new("file.tar")));
+        assert!(path.set_extension("xz"));
+        assert_eq!(path, PathBuf::from("/base/dir/file.tar.xz"));
+        path.set_file_name("other.rs");
+        assert!(path.ends_with("dir/other.rs"));
+        assert!(path.pop());
+        assert!(path.starts_with("/base"));
+        assert_eq!(path.ancestors().count(), 3);
+        assert_eq!(path.components().count(), 3);
+        assert_eq!(path.with_extension("d"), PathBuf::from("/base/dir.d"));
+        path.extend(["a", "b"]);
+        assert_eq!(path, ["/base", "dir", "a", "b"].iter().collect());
+    }
+
+    #[test]
+    fn file_system_queries() -> std::io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let file = PathBuf::from(root.path().join("file.txt"));
+        std::fs::write(&file, "content")?;
+        let dir = PathBuf::from(root.path());
+        let missing = dir.join("missing");
+
+        // These are synchronous, with and without the `async-std` feature
+        assert!(file.is_file() && !file.is_dir() && file.exists());
+        assert!(dir.is_dir() && !dir.is_file());
+        assert!(!missing.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn deref_to_async_path() {
+        fn takes_path(path: &InnerPath) -> usize {
+            path.iter().count()
+        }
+        let path = PathBuf::from("a/b/c");
+        assert_eq!(takes_path(&path), 3);
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Config {
+        path: PathBuf,
+        #[serde(with = "lossless")]
+        std_path: StdPathBuf,
+        #[serde(with = "string_only")]
+        utf8_path: PathBuf,
+        #[serde(with = "lossy")]
+        lossy_path: PathBuf,
+    }
+
+    #[cfg(unix)]
+    fn non_utf8() -> PathBuf {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(InnerPathBuf::from(OsString::from_vec(
+            b"dir/caf\xe9".to_vec(),
+        )))
+    }
+
+    fn config(path: &PathBuf) -> Config {
+        Config {
+            path: path.clone(),
+            std_path: path.into(),
+            utf8_path: PathBuf::from("dir/café"),
+            lossy_path: PathBuf::from("dir/file"),
+        }
+    }
+
+    fn check_round_trips(path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        let original = config(path);
+        let json = serde_json::to_string(&original)?;
+        assert_eq!(serde_json::from_str::<Config>(&json)?, original);
+        let toml = toml::to_string(&original)?;
+        assert_eq!(toml::from_str::<Config>(&toml)?, original);
+        let bincode = bincode::serialize(&original)?;
+        assert_eq!(bincode::deserialize::<Config>(&bincode)?, original);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_utf8() -> Result<(), Box<dyn std::error::Error>> {
+        let path = PathBuf::from("dir/café.txt");
+        assert_eq!(serde_json::to_string(&path)?, r#""dir/café.txt""#);
+        check_round_trips(&path)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn round_trip_non_utf8() -> Result<(), Box<dyn std::error::Error>> {
+        let path = non_utf8();
+        assert_eq!(
+            serde_json::to_string(&path)?,
+            r#"{"bytes":[100,105,114,47,99,97,102,233]}"#
+        );
+        check_round_trips(&path)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn string_representations() -> Result<(), Box<dyn std::error::Error>> {
+        let mut serializer = serde_json::Serializer::new(Vec::new());
+        assert!(string_only::serialize(&non_utf8(), &mut serializer).is_err());
+
+        let mut serializer = serde_json::Serializer::new(Vec::new());
+        lossy::serialize(&non_utf8(), &mut serializer)?;
+        let json = String::from_utf8(serializer.into_inner())?;
+        assert_eq!(json, "\"dir/caf\u{fffd}\"");
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let path: PathBuf = lossy::deserialize(&mut deserializer)?;
+        assert_eq!(path, PathBuf::from("dir/caf\u{fffd}"));
+        Ok(())
     }
 }
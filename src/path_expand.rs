@@ -0,0 +1,470 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Expands the home directory (`~`, `~user`)
+//! and environment variables (`$VAR`, `${VAR}`, `${VAR:-default}`)
+//! in paths, like a shell would,
+//! e.g. `~/data/$PROJECT/out.csv`.
+//!
+//! A `$` that is not followed by a variable name or `{` is kept as is.
+//! The default value of `${VAR:-default}` is used
+//! if `VAR` is undefined or empty,
+//! and may itself contain variables.
+
+use std::{
+    ffi::OsString,
+    path::{PathBuf as StdPathBuf, is_separator},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, de::Error as _};
+
+use crate::StreamIdent;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Environment variable '{0}' is not defined")]
+    UndefinedVariable(String),
+
+    #[error("Invalid variable name '{1}' in '{0}'")]
+    InvalidVariableName(String, String),
+
+    #[error("Missing closing '}}' in '{0}'")]
+    UnclosedBrace(String),
+
+    #[error("Failed to find the home directory of the current user")]
+    NoHomeDir,
+
+    #[error("Failed to find the home directory of user '{0}'")]
+    UnknownUser(String),
+}
+
+/// What to do with parts that can not be expanded,
+/// i.e. undefined variables and unknown home directories.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Keeps such parts as they are
+    #[default]
+    Lenient,
+    /// Fails with an error
+    Strict,
+}
+
+struct Expander<'a> {
+    mode: Mode,
+    vars: &'a dyn Fn(&str) -> Option<OsString>,
+    homes: &'a dyn Fn(Option<&str>) -> Option<StdPathBuf>,
+}
+
+const fn is_name_start(chr: char) -> bool {
+    chr.is_ascii_alphabetic() || chr == '_'
+}
+
+const fn is_name_char(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || chr == '_'
+}
+
+/// Splits `${...}` content from the rest of the input,
+/// respecting nested `{...}` pairs, e.g. `${A:-${B}}` or `${A:-{x}}`.
+/// `after_brace` is the input following the opening `${`.
+fn split_braced(after_brace: &str) -> Option<(&str, &str)> {
+    let mut depth = 0_usize;
+    for (idx, chr) in after_brace.char_indices() {
+        match chr {
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                let (body, close_and_rest) = after_brace.split_at(idx);
+                return Some((body, close_and_rest.strip_prefix('}')?));
+            }
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Expander<'_> {
+    fn expand(&self, input: &str) -> Result<OsString, Error> {
+        let mut out = OsString::with_capacity(input.len());
+        let rest = self.expand_tilde(input, &mut out)?;
+        self.expand_vars(rest, &mut out)?;
+        Ok(out)
+    }
+
+    /// Expands a leading `~` or `~user`,
+    /// and returns the remaining input.
+    fn expand_tilde<'b>(&self, input: &'b str, out: &mut OsString) -> Result<&'b str, Error> {
+        let Some(after_tilde) = input.strip_prefix('~') else {
+            return Ok(input);
+        };
+        let (user, rest) =
+            after_tilde.split_at(after_tilde.find(is_separator).unwrap_or(after_tilde.len()));
+        let user_opt = Some(user).filter(|name| !name.is_empty());
+        match ((self.homes)(user_opt), self.mode) {
+            (Some(home), _) => {
+                out.push(home);
+                Ok(rest)
+            }
+            (None, Mode::Lenient) => Ok(input),
+            (None, Mode::Strict) => {
+                Err(user_opt.map_or(Error::NoHomeDir, |name| Error::UnknownUser(name.to_owned())))
+            }
+        }
+    }
+
+    fn expand_vars(&self, input: &str, out: &mut OsString) -> Result<(), Error> {
+        let mut rest = input;
+        while let Some(dollar_idx) = rest.find('$') {
+            let (literal, from_dollar) = rest.split_at(dollar_idx);
+            out.push(literal);
+            let after_dollar = from_dollar.strip_prefix('$').unwrap_or(from_dollar);
+            rest = if let Some(after_brace) = after_dollar.strip_prefix('{') {
+                let (body, after_close) = split_braced(after_brace)
+                    .ok_or_else(|| Error::UnclosedBrace(input.to_owned()))?;
+                self.expand_braced(input, body, out)?;
+                after_close
+            } else {
+                let name_len = if after_dollar.starts_with(is_name_start) {
+                    after_dollar
+                        .find(|chr: char| !is_name_char(chr))
+                        .unwrap_or(after_dollar.len())
+                } else {
+                    0
+                };
+                let (name, after_name) = after_dollar.split_at(name_len);
+                if name.is_empty() {
+                    out.push("$");
+                } else {
+                    self.push_var(name, from_dollar.split_at(name_len + 1).0, out)?;
+                }
+                after_name
+            };
+        }
+        out.push(rest);
+        Ok(())
+    }
+
+    /// Expands the content of `${...}`.
+    fn expand_braced(&self, input: &str, body: &str, out: &mut OsString) -> Result<(), Error> {
+        let (name, default_opt) = body
+            .split_once(":-")
+            .map_or((body, None), |(name, default)| (name, Some(default)));
+        if !name.starts_with(is_name_start) || !name.chars().all(is_name_char) {
+            return Err(Error::InvalidVariableName(
+                input.to_owned(),
+                name.to_owned(),
+            ));
+        }
+        match default_opt {
+            Some(default) => match (self.vars)(name).filter(|value| !value.is_empty()) {
+                Some(value) => out.push(value),
+                None => self.expand_vars(default, out)?,
+            },
+            None => self.push_var(name, &format!("${{{body}}}"), out)?,
+        }
+        Ok(())
+    }
+
+    /// Pushes the value of the variable `name`,
+    /// or `original` if it is undefined and we are lenient.
+    fn push_var(&self, name: &str, original: &str, out: &mut OsString) -> Result<(), Error> {
+        match ((self.vars)(name), self.mode) {
+            (Some(value), _) => out.push(value),
+            (None, Mode::Lenient) => out.push(original),
+            (None, Mode::Strict) => return Err(Error::UndefinedVariable(name.to_owned())),
+        }
+        Ok(())
+    }
+}
+
+/// Finds the home directory of `user` in the contents of `/etc/passwd`.
+#[cfg(unix)]
+fn passwd_home(passwd: &str, user: &str) -> Option<StdPathBuf> {
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? == user {
+            fields.nth(4).map(StdPathBuf::from)
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the home directory of `user`.
+///
+/// NOTE Users are looked up in `/etc/passwd` only,
+///      so users provided by e.g. LDAP are not found.
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<StdPathBuf> {
+    std::fs::read_to_string("/etc/passwd")
+        .ok()
+        .and_then(|passwd| passwd_home(&passwd, user))
+}
+
+#[cfg(not(unix))]
+const fn user_home_dir(_user: &str) -> Option<StdPathBuf> {
+    None
+}
+
+/// Returns the home directory of the current user (`None`),
+/// or of the given one.
+fn home_dir(user: Option<&str>) -> Option<StdPathBuf> {
+    user.map_or_else(std::env::home_dir, user_home_dir)
+}
+
+/// Expands `~`, `~user`, `$VAR`, `${VAR}` and `${VAR:-default}` in `input`,
+/// using the environment of the current process.
+///
+/// # Errors
+///
+/// If `input` contains an invalid `${...}`,
+/// or in [`Mode::Strict`],
+/// if a variable is undefined or a home directory could not be found.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// use cli_utils_hoijui::path_expand::{expand, Mode};
+///
+/// let expanded = expand("${CLI_UTILS_UNDEFINED:-/tmp}/out.csv", Mode::Strict)?;
+/// assert_eq!(expanded, Path::new("/tmp/out.csv"));
+/// assert!(expand("$CLI_UTILS_UNDEFINED/out.csv", Mode::Strict).is_err());
+/// # Ok::<(), cli_utils_hoijui::path_expand::Error>(())
+/// ```
+pub fn expand(input: &str, mode: Mode) -> Result<StdPathBuf, Error> {
+    expand_with(input, mode, &|name| std::env::var_os(name))
+}
+
+/// Like [`expand`], but takes the variables from `vars`
+/// instead of the environment.
+///
+/// # Errors
+///
+/// See [`expand`].
+pub fn expand_with(
+    input: &str,
+    mode: Mode,
+    vars: &dyn Fn(&str) -> Option<OsString>,
+) -> Result<StdPathBuf, Error> {
+    let expander = Expander {
+        mode,
+        vars,
+        homes: &home_dir,
+    };
+    expander.expand(input).map(StdPathBuf::from)
+}
+
+/// Types containing paths that can be [`expand`]ed.
+///
+/// Paths that are not valid UTF-8 are left as they are.
+pub trait Expand: Sized {
+    /// Expands the contained paths, see [`expand`].
+    ///
+    /// # Errors
+    ///
+    /// See [`expand`].
+    fn expanded(self, mode: Mode) -> Result<Self, Error>;
+}
+
+impl Expand for StdPathBuf {
+    fn expanded(self, mode: Mode) -> Result<Self, Error> {
+        if let Some(input) = self.to_str() {
+            return expand(input, mode);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl Expand for async_std::path::PathBuf {
+    fn expanded(self, mode: Mode) -> Result<Self, Error> {
+        StdPathBuf::from(self.into_os_string())
+            .expanded(mode)
+            .map(Self::from)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Expand for crate::path_buf::PathBuf {
+    fn expanded(self, mode: Mode) -> Result<Self, Error> {
+        StdPathBuf::from(self).expanded(mode).map(Self::from)
+    }
+}
+
+impl Expand for StreamIdent {
+    fn expanded(self, mode: Mode) -> Result<Self, Error> {
+        match self {
+            Self::Path(path, r#in) => Ok(Self::Path(path.expanded(mode)?, r#in)),
+            Self::StdIn | Self::StdOut => Ok(self),
+        }
+    }
+}
+
+impl<T: Expand> Expand for Option<T> {
+    fn expanded(self, mode: Mode) -> Result<Self, Error> {
+        self.map(|inner| inner.expanded(mode)).transpose()
+    }
+}
+
+/// Deserializes and then [`expand`]s in [`Mode::Lenient`],
+/// e.g. a path or a [`StreamIdent`].
+///
+/// Use as `#[serde(deserialize_with = "cli_utils_hoijui::path_expand::deserialize")]`.
+///
+/// # Errors
+///
+/// If deserializing or expanding fails.
+#[cfg(feature = "serde")]
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Expand,
+{
+    T::deserialize(deserializer)?
+        .expanded(Mode::Lenient)
+        .map_err(D::Error::custom)
+}
+
+/// Like [`deserialize`], but in [`Mode::Strict`].
+///
+/// # Errors
+///
+/// If deserializing or expanding fails.
+#[cfg(feature = "serde")]
+pub fn deserialize_strict<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Expand,
+{
+    T::deserialize(deserializer)?
+        .expanded(Mode::Strict)
+        .map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vars(name: &str) -> Option<OsString> {
+        match name {
+            "PROJECT" => Some("proj".into()),
+            "EMPTY" => Some(OsString::new()),
+            "NESTED" => Some("$PROJECT".into()),
+            _ => None,
+        }
+    }
+
+    fn test_homes(user: Option<&str>) -> Option<StdPathBuf> {
+        match user {
+            None => Some("/home/me".into()),
+            Some("bob") => Some("/home/bob".into()),
+            Some(_) => None,
+        }
+    }
+
+    fn expand_test(input: &str, mode: Mode) -> Result<String, Error> {
+        let expander = Expander {
+            mode,
+            vars: &test_vars,
+            homes: &test_homes,
+        };
+        expander
+            .expand(input)
+            .map(|expanded| expanded.to_string_lossy().into_owned())
+    }
+
+    macro_rules! expand_tests {
+        ($($name:ident: $input:expr => $lenient:expr, $strict:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let lenient: Result<&str, Error> = $lenient;
+                let strict: Result<&str, Error> = $strict;
+                assert_eq!(expand_test($input, Mode::Lenient), lenient.map(str::to_owned));
+                assert_eq!(expand_test($input, Mode::Strict), strict.map(str::to_owned));
+            }
+        )*
+        }
+    }
+
+    expand_tests! {
+        plain: "a/b" => Ok("a/b"), Ok("a/b"),
+        home: "~/data" => Ok("/home/me/data"), Ok("/home/me/data"),
+        home_only: "~" => Ok("/home/me"), Ok("/home/me"),
+        home_user: "~bob/x" => Ok("/home/bob/x"), Ok("/home/bob/x"),
+        home_unknown_user: "~eve/x" => Ok("~eve/x"), Err(Error::UnknownUser("eve".to_owned())),
+        tilde_inside: "a/~/b" => Ok("a/~/b"), Ok("a/~/b"),
+        var: "~/data/$PROJECT/out.csv" => Ok("/home/me/data/proj/out.csv"), Ok("/home/me/data/proj/out.csv"),
+        var_braced: "${PROJECT}_x" => Ok("proj_x"), Ok("proj_x"),
+        var_name_end: "$PROJECT.d" => Ok("proj.d"), Ok("proj.d"),
+        var_undefined: "a/$NOPE/b" => Ok("a/$NOPE/b"), Err(Error::UndefinedVariable("NOPE".to_owned())),
+        var_undefined_braced: "${NOPE}" => Ok("${NOPE}"), Err(Error::UndefinedVariable("NOPE".to_owned())),
+        var_not_recursive: "$NESTED" => Ok("$PROJECT"), Ok("$PROJECT"),
+        default_unused: "${PROJECT:-x}" => Ok("proj"), Ok("proj"),
+        default_undefined: "${NOPE:-x}/y" => Ok("x/y"), Ok("x/y"),
+        default_empty: "${EMPTY:-x}" => Ok("x"), Ok("x"),
+        default_nested: "${NOPE:-${PROJECT}/$PROJECT}" => Ok("proj/proj"), Ok("proj/proj"),
+        default_braces: "${NOPE:-{x}}/y" => Ok("{x}/y"), Ok("{x}/y"),
+        dollar_literal: "price$/$1" => Ok("price$/$1"), Ok("price$/$1"),
+        unclosed: "${PROJECT" => Err(Error::UnclosedBrace("${PROJECT".to_owned())), Err(Error::UnclosedBrace("${PROJECT".to_owned())),
+        invalid_name: "${1A}" => Err(Error::InvalidVariableName("${1A}".to_owned(), "1A".to_owned())), Err(Error::InvalidVariableName("${1A}".to_owned(), "1A".to_owned())),
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn passwd_lookup() {
+        let passwd = "root:x:0:0:root:/root:/bin/sh\nbob:x:1000:1000:Bob,,,:/home/bob:/bin/bash\n";
+        assert_eq!(
+            passwd_home(passwd, "bob"),
+            Some(StdPathBuf::from("/home/bob"))
+        );
+        assert_eq!(passwd_home(passwd, "bo"), None);
+    }
+
+    #[test]
+    fn expand_stream_ident() -> Result<(), Error> {
+        let ident = StreamIdent::Path("${CLI_UTILS_UNDEFINED:-out}/x".into(), false);
+        assert_eq!(
+            ident.expanded(Mode::Strict)?,
+            StreamIdent::Path("out/x".into(), false)
+        );
+        assert_eq!(
+            StreamIdent::StdIn.expanded(Mode::Strict)?,
+            StreamIdent::StdIn
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    #[allow(clippy::literal_string_with_formatting_args)]
+    fn deserialize_with() -> Result<(), serde_json::Error> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "deserialize")]
+            lenient: StdPathBuf,
+            #[serde(deserialize_with = "deserialize_strict")]
+            strict: Option<StreamIdent>,
+        }
+
+        let config: Config = serde_json::from_str(
+            r#"{
+                "lenient": "$CLI_UTILS_UNDEFINED/x",
+                "strict": { "Path": ["${CLI_UTILS_UNDEFINED:-out}/y", false] }
+            }"#,
+        )?;
+        assert_eq!(config.lenient, StdPathBuf::from("$CLI_UTILS_UNDEFINED/x"));
+        assert_eq!(
+            config.strict,
+            Some(StreamIdent::Path("out/y".into(), false))
+        );
+
+        let strict_res = serde_json::from_str::<Config>(
+            r#"{ "lenient": "", "strict": { "Path": ["$CLI_UTILS_UNDEFINED", true] } }"#,
+        );
+        assert!(strict_res.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Lexical path manipulation,
+//! meaning none of these functions access the file system.
+//!
+//! Because symlinks are not resolved,
+//! `a/link/..` is normalized to `a`,
+//! even if `link` points somewhere else.
+//! Use [`std::fs::canonicalize`] where that matters.
+//!
+//! All of these take anything that can be viewed as a [`std::path::Path`],
+//! including [`async_std`](https://docs.rs/async-std) paths
+//! and our own `path_buf::PathBuf`.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Removes `.` components and resolves `..` components
+/// against the preceding ones.
+///
+/// Leading `..` components of relative paths are kept,
+/// while those directly following the root are dropped,
+/// as `/..` is `/`.
+/// An empty result is returned as `.`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// use cli_utils_hoijui::path_utils::normalize;
+///
+/// assert_eq!(normalize("a/./b/../c/"), Path::new("a/c"));
+/// assert_eq!(normalize("../a/../../b"), Path::new("../../b"));
+/// assert_eq!(normalize("/../a"), Path::new("/a"));
+/// assert_eq!(normalize("a/.."), Path::new("."));
+/// ```
+#[must_use]
+pub fn normalize<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut components: Vec<Component<'_>> = Vec::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                Some(Component::ParentDir | Component::CurDir) | None => {
+                    components.push(component);
+                }
+            },
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                components.push(component);
+            }
+        }
+    }
+    if components.is_empty() {
+        return PathBuf::from(Component::CurDir.as_os_str());
+    }
+    components.iter().collect()
+}
+
+/// Returns the relative path that leads from `base` to `path`,
+/// e.g. `../../docs/x.md` from `src/bin` to `docs/x.md`.
+///
+/// Both are [`normalize`]d first,
+/// and `base` is taken to be a directory.
+///
+/// Returns `None` if there is no such path (lexically),
+/// which is the case if only one of them is absolute,
+/// if they are on different Windows drives,
+/// or if `base` has more leading `..` components than they share.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// use cli_utils_hoijui::path_utils::diff_paths;
+///
+/// assert_eq!(diff_paths("docs/x.md", "src/bin"), Some("../../docs/x.md".into()));
+/// assert_eq!(diff_paths("/a/b", "/a/b"), Some(".".into()));
+/// assert_eq!(diff_paths("a", "../b"), None);
+/// ```
+#[must_use]
+pub fn diff_paths<P, B>(path: P, base: B) -> Option<PathBuf>
+where
+    P: AsRef<Path>,
+    B: AsRef<Path>,
+{
+    let norm_path = normalize(path);
+    let norm_base = normalize(base);
+    let mut path_comps = norm_path
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .peekable();
+    let mut base_comps = norm_base
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .peekable();
+    while let (Some(path_comp), Some(base_comp)) = (path_comps.peek(), base_comps.peek()) {
+        if path_comp != base_comp {
+            break;
+        }
+        path_comps.next();
+        base_comps.next();
+    }
+
+    let mut relative = PathBuf::new();
+    for base_comp in base_comps {
+        match base_comp {
+            Component::Normal(_) => relative.push(Component::ParentDir),
+            Component::Prefix(_)
+            | Component::RootDir
+            | Component::ParentDir
+            | Component::CurDir => {
+                return None;
+            }
+        }
+    }
+    for path_comp in path_comps {
+        match path_comp {
+            Component::Normal(_) | Component::ParentDir | Component::CurDir => {
+                relative.push(path_comp);
+            }
+            Component::Prefix(_) | Component::RootDir => return None,
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(Component::CurDir);
+    }
+    Some(relative)
+}
+
+/// Resolves `path` against `base` instead of the current directory,
+/// and [`normalize`]s the result.
+///
+/// Absolute paths are only normalized.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// use cli_utils_hoijui::path_utils::resolve;
+///
+/// assert_eq!(resolve("../x.md", "/project/docs"), Path::new("/project/x.md"));
+/// assert_eq!(resolve("/etc/./x", "/project"), Path::new("/etc/x"));
+/// ```
+#[must_use]
+pub fn resolve<P, B>(path: P, base: B) -> PathBuf
+where
+    P: AsRef<Path>,
+    B: AsRef<Path>,
+{
+    normalize(base.as_ref().join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! normalize_tests {
+        ($($name:ident: $path:expr => $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                assert_eq!(normalize($path), Path::new($expected));
+            }
+        )*
+        }
+    }
+
+    normalize_tests! {
+        normalize_plain: "a/b" => "a/b",
+        normalize_cur_dir: "./a/./b/." => "a/b",
+        normalize_parent: "a/b/../c" => "a/c",
+        normalize_leading_parents: "../../a" => "../../a",
+        normalize_parents_beyond: "a/../../b" => "../b",
+        normalize_root_parent: "/../../a" => "/a",
+        normalize_to_empty: "a/b/../.." => ".",
+        normalize_empty: "" => ".",
+        normalize_trailing_slash: "a/b/" => "a/b",
+    }
+
+    macro_rules! diff_tests {
+        ($($name:ident: $path:expr, $base:expr => $expected:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let expected: Option<&str> = $expected;
+                assert_eq!(diff_paths($path, $base), expected.map(PathBuf::from));
+            }
+        )*
+        }
+    }
+
+    diff_tests! {
+        diff_sibling: "docs/x.md", "src/bin" => Some("../../docs/x.md"),
+        diff_child: "a/b/c", "a" => Some("b/c"),
+        diff_parent: "a", "a/b/c" => Some("../.."),
+        diff_same: "a/b", "a/./b" => Some("."),
+        diff_absolute: "/usr/share/doc", "/usr/lib" => Some("../share/doc"),
+        diff_path_parents: "../x", "a" => Some("../../x"),
+        diff_base_parents: "a", "../b" => None,
+        diff_shared_parents: "../a/x", "../a/y" => Some("../x"),
+        diff_absolute_path: "/a", "b" => None,
+        diff_absolute_base: "a", "/b" => None,
+        diff_unnormalized: "a/b/../c", "a/d/./e" => Some("../../c"),
+    }
+
+    #[test]
+    fn resolve_against_base() {
+        assert_eq!(resolve("x.md", "docs"), Path::new("docs/x.md"));
+        assert_eq!(resolve("../../x.md", "docs"), Path::new("../x.md"));
+        assert_eq!(resolve(".", "/base/./dir"), Path::new("/base/dir"));
+        assert_eq!(resolve("/abs/../y", "/base"), Path::new("/y"));
+    }
+}
@@ -2,11 +2,21 @@
 //
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
 use crate::BoxError;
 use thiserror::Error;
 
 /// This serves to wrap/represent `std::**()` and `Option` return values as `Result`s,
 /// like the one of [`std::path::Path::file_name()`], or [`std::ffi::OsStr::to_str()`].
+///
+/// Prefer the variants carrying context,
+/// as created by [`OptionExt`], [`ToStrExt`] and [`ResultExt`],
+/// over the plain [`Self::None`] and [`Self::NotValidUtf8`].
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Represents a `Option::None` value as an error.")]
@@ -20,10 +30,33 @@ pub enum Error {
     )]
     NotValidUtf8,
 
+    /// Like [`Self::None`], for a value derived from a path,
+    /// e.g. its file name.
+    #[error("Path '{}' has no such part (e.g. file name, parent or extension)", .path.display())]
+    NoneForPath { path: PathBuf },
+
+    /// Like [`Self::NotValidUtf8`], including the offending value.
+    #[error("Not valid UTF-8: {value:?}")]
+    InvalidUtf8 { value: OsString },
+
+    /// Wraps an other error, adding the operation and the path it failed on;
+    /// the wrapped error is only available as [`std::error::Error::source`].
+    #[error("Failed to {operation} '{}'", .path.display())]
+    Context {
+        operation: Cow<'static, str>,
+        path: PathBuf,
+        #[source]
+        error: Box<Self>,
+    },
+
     #[cfg(feature = "url_parse_error")]
     #[error(transparent)]
     InvalidUrl(#[from] url::ParseError),
 
+    #[cfg(feature = "validated_path")]
+    #[error(transparent)]
+    InvalidPath(#[from] crate::validated_path::Error),
+
     /// Represents all cases of `std::io::Error`.
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -32,3 +65,123 @@ pub enum Error {
     #[error(transparent)]
     Boxed(#[from] BoxError),
 }
+
+/// Converts `None` into an [`Error`] that records the path it was derived from.
+pub trait OptionExt<T> {
+    /// # Errors
+    ///
+    /// If `self` is `None`, returns [`Error::NoneForPath`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::path::Path;
+    /// use cli_utils_hoijui::std_error::OptionExt;
+    ///
+    /// let path = Path::new("/");
+    /// let err = path.file_name().ok_or_path_error(path).unwrap_err();
+    /// assert!(err.to_string().contains("'/'"));
+    /// ```
+    fn ok_or_path_error<P: AsRef<Path>>(self, path: P) -> Result<T, Error>;
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    fn ok_or_path_error<P: AsRef<Path>>(self, path: P) -> Result<T, Error> {
+        self.ok_or_else(|| Error::NoneForPath {
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+}
+
+/// Like `to_str()`, but fails with an [`Error`] recording the offending value.
+pub trait ToStrExt {
+    /// # Errors
+    ///
+    /// If `self` is not valid UTF-8, returns [`Error::InvalidUtf8`].
+    fn to_str_or_error(&self) -> Result<&str, Error>;
+}
+
+impl ToStrExt for OsStr {
+    fn to_str_or_error(&self) -> Result<&str, Error> {
+        self.to_str().ok_or_else(|| Error::InvalidUtf8 {
+            value: self.to_os_string(),
+        })
+    }
+}
+
+impl ToStrExt for Path {
+    fn to_str_or_error(&self) -> Result<&str, Error> {
+        self.as_os_str().to_str_or_error()
+    }
+}
+
+/// Adds context to errors convertible into an [`Error`].
+pub trait ResultExt<T> {
+    /// Wraps the error in an [`Error::Context`],
+    /// e.g. `fs::read(&path).with_path_context("read", &path)?`.
+    ///
+    /// # Errors
+    ///
+    /// If `self` is an error.
+    fn with_path_context<O, P>(self, operation: O, path: P) -> Result<T, Error>
+    where
+        O: Into<Cow<'static, str>>,
+        P: AsRef<Path>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+    fn with_path_context<O, P>(self, operation: O, path: P) -> Result<T, Error>
+    where
+        O: Into<Cow<'static, str>>,
+        P: AsRef<Path>,
+    {
+        self.map_err(|err| Error::Context {
+            operation: operation.into(),
+            path: path.as_ref().to_path_buf(),
+            error: Box::new(err.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context() {
+        let path = Path::new("dir/..");
+        let res = path
+            .file_name()
+            .ok_or_path_error(path)
+            .with_path_context("get the file name of", path);
+        let Err(err) = res else {
+            panic!("there should be no file name");
+        };
+        assert_eq!(err.to_string(), "Failed to get the file name of 'dir/..'");
+        let source = std::error::Error::source(&err).map(ToString::to_string);
+        assert_eq!(
+            source.as_deref(),
+            Some("Path 'dir/..' has no such part (e.g. file name, parent or extension)")
+        );
+
+        let io_res = std::fs::read(Path::new("/surely/not/existing"))
+            .with_path_context("read", "/surely/not/existing");
+        assert!(matches!(
+            io_res,
+            Err(Error::Context { error, .. }) if matches!(*error, Error::IO(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let os_str = OsStr::from_bytes(b"caf\xe9");
+        assert_eq!(
+            os_str.to_str_or_error().map_err(|err| err.to_string()),
+            Err(r#"Not valid UTF-8: "caf\xE9""#.to_owned())
+        );
+        assert_eq!(Path::new("cafe").to_str_or_error().ok(), Some("cafe"));
+    }
+}
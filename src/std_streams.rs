@@ -8,25 +8,29 @@ use std::borrow::Cow;
 use std::str::FromStr;
 use std::sync::LazyLock;
 
-#[cfg(all(feature = "async", feature = "serde"))]
+#[cfg(feature = "serde")]
 use crate::path_buf::PathBuf;
-#[cfg(all(feature = "async", not(feature = "serde")))]
+#[cfg(feature = "async-std")]
+use async_std::path::Path;
+#[cfg(all(feature = "async-std", not(feature = "serde")))]
 use async_std::path::PathBuf;
-#[cfg(not(feature = "async"))]
+use std::io;
+#[cfg(not(feature = "async-std"))]
+use std::path::Path;
+#[cfg(not(any(feature = "async-std", feature = "serde")))]
 use std::path::PathBuf;
 #[cfg(feature = "async")]
 use {
-    async_std::fs::File,
-    async_std::io::BufReadExt,
-    async_std::io::{self, BufRead, BufReader, Write, WriteExt},
-    async_std::path::Path,
-    async_std::stream::Stream,
+    crate::async_runtime,
+    futures::Stream,
+    futures::io::{
+        AsyncBufRead as BufRead, AsyncBufReadExt, AsyncWrite as Write, AsyncWriteExt, BufReader,
+    },
 };
 #[cfg(not(feature = "async"))]
 use {
     std::fs::File,
-    std::io::{self, BufRead, BufReader, Write},
-    std::path::Path,
+    std::io::{BufRead, BufReader, Write},
 };
 
 pub const STREAM_PATH_STR: &str = "-";
@@ -109,12 +113,12 @@ impl StreamIdent {
     ///
     /// ```rust
     /// # use std::io;
-    /// # use async_std::path::Path;
-    /// # use async_std::path::PathBuf;
+    /// # use std::path::Path;
+    /// # use std::path::PathBuf;
     /// # use std::str::FromStr;
     /// use cli_utils_hoijui::StreamIdent;
     /// #[cfg(feature = "async")]
-    /// use async_std::io::BufReadExt;
+    /// use futures::io::AsyncBufReadExt;
     ///
     /// # #[cfg(feature = "async")]
     /// # async fn create_input_reader_example() -> io::Result<()> {
@@ -196,7 +200,7 @@ impl StreamIdent {
     /// let in_stream_ident = StreamIdent::from_path_opt(Some(path_buf.as_path()), true); // reads from file "$CWD/my_dir/my_file.txt"
     /// let mut reader = in_stream_ident.create_input_reader()?;
     ///
-    /// let in_stream_ident = StreamIdent::from_path_buf_opt(Some(path_buf), true); // reads from file "$CWD/my_dir/my_file.txt"
+    /// let in_stream_ident = StreamIdent::from_path_buf_opt(Some(path_buf.into()), true); // reads from file "$CWD/my_dir/my_file.txt"
     /// let mut reader = in_stream_ident.create_input_reader()?;
     ///
     /// let mut buffer = String::new();
@@ -236,7 +240,8 @@ impl StreamIdent {
     pub async fn create_input_reader_file<P: AsRef<Path> + ?Sized + Send + Sync>(
         file_path: &P,
     ) -> io::Result<Box<dyn BufRead + Unpin>> {
-        let file = File::open(file_path).await?;
+        let file =
+            async_runtime::open(std::path::Path::new(file_path.as_ref().as_os_str())).await?;
         Ok(Box::new(BufReader::new(file)))
     }
 
@@ -259,7 +264,7 @@ impl StreamIdent {
     #[must_use]
     #[cfg(feature = "async")]
     pub fn create_input_reader_stdin() -> Box<dyn BufRead + Unpin> {
-        Box::new(BufReader::new(io::stdin()))
+        Box::new(BufReader::new(async_runtime::stdin()))
     }
 
     /// Creates a reader that reads from stdin.
@@ -278,12 +283,12 @@ impl StreamIdent {
     ///
     /// ```rust
     /// # use std::io;
-    /// # use async_std::path::Path;
-    /// # use async_std::path::PathBuf;
+    /// # use std::path::Path;
+    /// # use std::path::PathBuf;
     /// # use std::str::FromStr;
     /// use cli_utils_hoijui::StreamIdent;
     /// # #[cfg(feature = "async")]
-    /// use async_std::io::WriteExt;
+    /// use futures::io::AsyncWriteExt;
     ///
     /// # #[cfg(feature = "async")]
     /// # async fn create_output_writer_example() -> io::Result<()> {
@@ -344,7 +349,7 @@ impl StreamIdent {
     /// # use std::str::FromStr;
     /// use cli_utils_hoijui::StreamIdent;
     /// # #[cfg(feature = "async")]
-    /// use async_std::io::WriteExt;
+    /// use futures::io::AsyncWriteExt;
     ///
     /// # #[cfg(not(feature = "async"))]
     /// # fn create_output_writer_example() -> io::Result<()> {
@@ -366,7 +371,7 @@ impl StreamIdent {
     /// let out_stream_ident = StreamIdent::from_path_opt(Some(path_buf.as_path()), false); // writes to file "$CWD/my_dir/my_file.txt"
     /// let mut writer = out_stream_ident.create_output_writer()?;
     ///
-    /// let out_stream_ident = StreamIdent::from_path_buf_opt(Some(path_buf), false); // writes to file "$CWD/my_dir/my_file.txt"
+    /// let out_stream_ident = StreamIdent::from_path_buf_opt(Some(path_buf.into()), false); // writes to file "$CWD/my_dir/my_file.txt"
     /// let mut writer = out_stream_ident.create_output_writer()?;
     ///
     /// for line in lines {
@@ -402,7 +407,8 @@ impl StreamIdent {
     pub async fn create_output_writer_file<P: AsRef<Path> + ?Sized + Send + Sync>(
         file_path: &P,
     ) -> io::Result<Box<dyn Write + Unpin + Send + Sync>> {
-        let file = File::open(file_path).await?;
+        let file =
+            async_runtime::create(std::path::Path::new(file_path.as_ref().as_os_str())).await?;
         Ok(Box::new(file) as Box<dyn Write + Unpin + Send + Sync>)
     }
 
@@ -425,7 +431,7 @@ impl StreamIdent {
     #[cfg(feature = "async")]
     #[must_use]
     pub fn create_output_writer_stdout() -> Box<dyn Write + Unpin + Send + Sync> {
-        Box::new(io::stdout())
+        Box::new(async_runtime::stdout())
     }
     #[cfg(not(feature = "async"))]
     #[must_use]
@@ -517,12 +523,12 @@ pub fn remove_eol(line: &mut String) {
 /// # use std::io;
 /// use cli_utils_hoijui::lines_iterator;
 /// # #[cfg(feature = "async")]
-/// use async_std::io::BufReader;
+/// use futures::io::BufReader;
 /// # #[cfg(feature = "async")]
-/// use async_std::stream::StreamExt;
+/// use futures::StreamExt;
 ///
 /// # #[cfg(feature = "async")]
-/// # async fn lines_iterator_example<R: async_std::io::BufRead + Unpin>(reader: &mut BufReader<R>) -> io::Result<()> {
+/// # async fn lines_iterator_example<R: futures::io::AsyncBufRead + Unpin>(reader: &mut BufReader<R>) -> io::Result<()> {
 ///     let mut lines_stream = lines_iterator(reader, true);
 ///     while let Some(line) = lines_stream.next().await {
 ///         println!("{}", &line?)
@@ -536,7 +542,7 @@ pub fn remove_eol(line: &mut String) {
 /// - if `strip_eol` is `false`,
 ///   because that is not supported by the underlying function we use.
 #[cfg(feature = "async")]
-pub fn lines_iterator<R: async_std::io::BufRead + Unpin>(
+pub fn lines_iterator<R: BufRead + Unpin>(
     reader: &mut BufReader<R>,
     strip_eol: bool,
 ) -> impl Stream<Item = io::Result<String>> {
@@ -604,8 +610,7 @@ pub fn lines_iterator(
 ///
 /// ```rust
 /// # use std::io;
-/// # use async_std::path::Path;
-/// # use async_std::path::PathBuf;
+/// # use std::path::PathBuf;
 /// # use std::str::FromStr;
 /// use cli_utils_hoijui::StreamIdent;
 /// use cli_utils_hoijui::write_to_file;
@@ -638,6 +643,8 @@ pub async fn write_to_file<L: AsRef<str> + Send + Sync>(
         writer_pinned.write_all(line.as_ref().as_bytes()).await?;
         writer_pinned.write_all(b"\n").await?;
     }
+    // Async writers do not reliably write buffered data when dropped
+    writer_pinned.flush().await?;
 
     Ok(())
 }
@@ -662,7 +669,7 @@ pub async fn write_to_file<L: AsRef<str> + Send + Sync>(
 /// let out_stream_ident = StreamIdent::StdOut; // writes to stdout
 /// write_to_file(&lines, &out_stream_ident)?;
 ///
-/// let out_stream_ident = StreamIdent::Path(PathBuf::from("my_dir/my_file.txt"), false); // writes to file "$CWD/my_dir/my_file.txt"
+/// let out_stream_ident = StreamIdent::Path(PathBuf::from("my_dir/my_file.txt").into(), false); // writes to file "$CWD/my_dir/my_file.txt"
 /// write_to_file(&lines, &out_stream_ident)?;
 /// # Ok(())
 /// # }
@@ -689,7 +696,7 @@ pub fn write_to_file<L: AsRef<str>>(
 #[cfg(test)]
 mod tests {
     #[cfg(feature = "async")]
-    use {async_std::io::BufReader, async_std::stream::Stream, async_std::stream::StreamExt};
+    use futures::StreamExt;
 
     use super::*;
 
@@ -961,4 +968,19 @@ mod tests {
         test_lines_iterator_strip_mixed_4:
             "line 1\r\nline 2\nline 3\r\n",
     }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_file_round_trip() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = StreamIdent::from_path(&dir.path().join("lines.txt"), false);
+        write_to_file(["line 1", "line 2"], &file).await?;
+        let StreamIdent::Path(path, _) = file else {
+            unreachable!("A file path is always a path");
+        };
+        let mut reader = BufReader::new(StreamIdent::create_input_reader_file(&path).await?);
+        let lines = try_concat_stream(lines_iterator(&mut reader, true)).await?;
+        assert_eq!(lines, ["line 1", "line 2"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,335 @@
+// SPDX-FileCopyrightText: 2026 Robin Vobruba <hoijui.quaero@gmail.com>
+//
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Wrappers around [`PathBuf`] that guarantee a property of the path,
+//! checked when they are created,
+//! which includes parsing ([`FromStr`]) and deserializing.
+//!
+//! This allows config structs to reject bad paths at parse time.
+//! Note that properties of the file system may change afterwards,
+//! e.g. an [`ExistingFile`] might get removed.
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io,
+    ops::Deref,
+    path::Path as StdPath,
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::path_buf::PathBuf;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Path '{0}' does not exist")]
+    NotFound(PathBuf),
+
+    #[error("Path '{0}' is not a file")]
+    NotAFile(PathBuf),
+
+    #[error("Path '{0}' is not a directory")]
+    NotADir(PathBuf),
+
+    #[error("Path '{0}' is not absolute")]
+    NotAbsolute(PathBuf),
+
+    #[error("Path '{0}' is not relative")]
+    NotRelative(PathBuf),
+
+    #[error("Directory '{0}' is not writable: {1}")]
+    NotWritable(PathBuf, io::Error),
+
+    #[error("Failed to access path '{0}': {1}")]
+    IO(PathBuf, io::Error),
+}
+
+/// Returns the metadata of `path`, following symlinks.
+fn metadata(path: &PathBuf) -> Result<fs::Metadata, Error> {
+    fs::metadata(StdPath::new(path.as_os_str())).map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            Error::NotFound(path.clone())
+        } else {
+            Error::IO(path.clone(), err)
+        }
+    })
+}
+
+fn check_existing_file(path: &PathBuf) -> Result<(), Error> {
+    if metadata(path)?.is_file() {
+        Ok(())
+    } else {
+        Err(Error::NotAFile(path.clone()))
+    }
+}
+
+fn check_existing_dir(path: &PathBuf) -> Result<(), Error> {
+    if metadata(path)?.is_dir() {
+        Ok(())
+    } else {
+        Err(Error::NotADir(path.clone()))
+    }
+}
+
+fn check_absolute(path: &PathBuf) -> Result<(), Error> {
+    if path.is_absolute() {
+        Ok(())
+    } else {
+        Err(Error::NotAbsolute(path.clone()))
+    }
+}
+
+fn check_relative(path: &PathBuf) -> Result<(), Error> {
+    if path.is_relative() {
+        Ok(())
+    } else {
+        Err(Error::NotRelative(path.clone()))
+    }
+}
+
+/// Distinguishes the probe files of concurrent checks within this process.
+static PROBE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// How often to try another probe file name,
+/// if the previous one already exists.
+const PROBE_ATTEMPTS: usize = 16;
+
+fn probe_name(count: u64) -> String {
+    format!(
+        ".{}-write-probe-{}-{count}",
+        env!("CARGO_PKG_NAME"),
+        std::process::id()
+    )
+}
+
+/// Checks whether we may create files in the directory `path`,
+/// by creating (and removing) an empty file in it,
+/// as permission bits alone do not tell (ACLs, read-only mounts, ...).
+///
+/// Failing to remove the probe file again is reported as [`Error::IO`].
+fn check_writable_dir(path: &PathBuf) -> Result<(), Error> {
+    check_existing_dir(path)?;
+    let dir = StdPath::new(path.as_os_str());
+    let mut last_err = None;
+    for _attempt in 0..PROBE_ATTEMPTS {
+        let probe = dir.join(probe_name(PROBE_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        match OpenOptions::new().write(true).create_new(true).open(&probe) {
+            Ok(_file) => {
+                return fs::remove_file(&probe)
+                    .map_err(|err| Error::IO(probe.as_path().into(), err));
+            }
+            // Left behind by a crashed process with the same ID
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => last_err = Some(err),
+            Err(err) => return Err(Error::NotWritable(path.clone(), err)),
+        }
+    }
+    let err = last_err.unwrap_or_else(|| io::Error::from(io::ErrorKind::AlreadyExists));
+    Err(Error::NotWritable(path.clone(), err))
+}
+
+macro_rules! validated_path {
+    ($(#[$doc:meta])* $name:ident, $check:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(try_from = "PathBuf", into = "PathBuf")]
+        pub struct $name(PathBuf);
+
+        impl $name {
+            /// # Errors
+            ///
+            /// If `path` does not have the required property,
+            /// or it could not be checked.
+            pub fn new<P: Into<PathBuf>>(path: P) -> Result<Self, Error> {
+                Self::try_from(path.into())
+            }
+
+            #[must_use]
+            pub const fn as_path_buf(&self) -> &PathBuf {
+                &self.0
+            }
+
+            #[must_use]
+            pub fn into_inner(self) -> PathBuf {
+                self.0
+            }
+        }
+
+        impl TryFrom<PathBuf> for $name {
+            type Error = Error;
+
+            fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+                $check(&path)?;
+                Ok(Self(path))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::try_from(PathBuf::from(s))
+            }
+        }
+
+        impl From<$name> for PathBuf {
+            fn from(path: $name) -> Self {
+                path.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = PathBuf;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl AsRef<StdPath> for $name {
+            fn as_ref(&self) -> &StdPath {
+                self.0.as_ref()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+validated_path!(
+    /// A path to an existing file (or a symlink to one).
+    ExistingFile,
+    check_existing_file
+);
+validated_path!(
+    /// A path to an existing directory (or a symlink to one).
+    ExistingDir,
+    check_existing_dir
+);
+validated_path!(
+    /// An absolute path; the file system is not accessed.
+    AbsolutePath,
+    check_absolute
+);
+validated_path!(
+    /// A relative path; the file system is not accessed.
+    RelativePath,
+    check_relative
+);
+validated_path!(
+    /// A path to an existing directory in which we may create files.
+    ///
+    /// This is checked by creating and removing an empty file in it.
+    WritableDir,
+    check_writable_dir
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existence() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let file = root.path().join("file.txt");
+        fs::write(&file, "content")?;
+        let missing = root.path().join("missing");
+
+        assert!(ExistingFile::new(file.as_path()).is_ok());
+        assert!(matches!(
+            ExistingFile::new(root.path()),
+            Err(Error::NotAFile(_))
+        ));
+        assert!(matches!(
+            ExistingFile::new(missing.as_path()),
+            Err(Error::NotFound(_))
+        ));
+        assert!(ExistingDir::new(root.path()).is_ok());
+        assert!(matches!(
+            ExistingDir::new(file.as_path()),
+            Err(Error::NotADir(_))
+        ));
+        assert!(WritableDir::new(root.path()).is_ok());
+        assert!(matches!(
+            WritableDir::new(missing.as_path()),
+            Err(Error::NotFound(_))
+        ));
+        // The probe file got removed again
+        assert_eq!(fs::read_dir(root.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn writable_dir_probes() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        // Stale probe files, as if left behind by a crashed process
+        let next = PROBE_COUNTER.load(Ordering::Relaxed);
+        for count in next..next + 4 {
+            fs::write(root.path().join(probe_name(count)), "")?;
+        }
+        let checks: Vec<_> = (0..8)
+            .map(|_| {
+                let dir = root.path().to_path_buf();
+                std::thread::spawn(move || WritableDir::new(dir.as_path()).is_ok())
+            })
+            .collect();
+        for check in checks {
+            assert_eq!(check.join().ok(), Some(true));
+        }
+        // Only the stale probe files are left
+        assert_eq!(fs::read_dir(root.path())?.count(), 4);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lexical() {
+        assert!("/a/b".parse::<AbsolutePath>().is_ok());
+        assert!(matches!(
+            "a/b".parse::<AbsolutePath>(),
+            Err(Error::NotAbsolute(_))
+        ));
+        assert!("a/b".parse::<RelativePath>().is_ok());
+        assert!(matches!(
+            "/a/b".parse::<RelativePath>(),
+            Err(Error::NotRelative(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Debug, Deserialize, Serialize)]
+        struct Config {
+            input: ExistingFile,
+            output: RelativePath,
+        }
+
+        let root = tempfile::tempdir()?;
+        let file = root.path().join("in.csv");
+        fs::write(&file, "")?;
+        let file_json = serde_json::to_string(&file)?;
+
+        let config: Config = serde_json::from_str(&format!(
+            r#"{{ "input": {file_json}, "output": "out.csv" }}"#
+        ))?;
+        assert_eq!(config.input.as_path_buf(), &PathBuf::from(file.as_path()));
+        assert_eq!(
+            serde_json::to_string(&config)?,
+            format!(r#"{{"input":{file_json},"output":"out.csv"}}"#)
+        );
+
+        let missing_res = serde_json::from_str::<Config>(
+            r#"{ "input": "/surely/not/existing.csv", "output": "out.csv" }"#,
+        );
+        let missing_err = missing_res.err().ok_or("missing input was accepted")?;
+        assert!(missing_err.to_string().contains("does not exist"));
+        Ok(())
+    }
+}